@@ -1,17 +1,23 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use egui::epaint::{CornerRadiusF32, CubicBezierShape, RectShape};
+use egui::epaint::{CornerRadiusF32, RectShape};
 use egui::*;
 
-use crate::color_hex_utils::*;
-use crate::utils::ColorUtils;
-
 use super::*;
 
 pub type PortLocations = std::collections::HashMap<AnyParameterId, Pos2>;
 pub type NodeRects = std::collections::HashMap<NodeId, Rect>;
 
 const DISTANCE_TO_CONNECT: f32 = 12.0;
+/// Screen space left between the editor rect and the framed nodes when using
+/// [`GraphEditorState::zoom_to_fit`] or [`GraphEditorState::frame_selection`].
+const FRAME_PADDING: f32 = 30.0;
+/// How far from a connection, in graph space, the cursor can be to hover it.
+const WIRE_HOVER_DISTANCE: f32 = 6.0;
+/// The distance between the centers of the titlebar buttons, unzoomed.
+const TITLEBAR_BUTTON_SPACING: f32 = 18.0;
+/// How far from a node, in graph space, its duplicate is placed.
+const DUPLICATE_OFFSET: f32 = 20.0;
 
 /// Nodes communicate certain events to the parent graph when drawn. There is
 /// one special `User` variant which can be used by users as the return value
@@ -40,13 +46,36 @@ pub enum NodeResponse<UserResponse: UserResponseTrait, NodeData: NodeDataTrait>
         output: OutputId,
         input: InputId,
     },
+    /// Emitted when a connection is clicked. Apps can use it to show
+    /// information about the connection.
+    WireClicked {
+        output: OutputId,
+        input: InputId,
+    },
     // PasteNodes,
+    /// Adds a copy of the node, without its connections, next to it. The copy
+    /// is reported with a `CreatedNode` response.
+    DuplicateNode(NodeId),
+    /// Removes all the connections to and from the node. Each of them is
+    /// reported with a `DisconnectEvent` response.
+    DisconnectAll(NodeId),
+    /// Collapses the node to its titlebar, or expands it back.
+    ToggleCollapsed(NodeId),
     /// Emitted when a node is interacted with, and should be raised
     RaiseNode(NodeId),
+    /// Emitted when a node is dragged. The `drag_delta` is in graph space.
     MoveNode {
         node: NodeId,
         drag_delta: Vec2,
     },
+    /// Emitted when the resize handle of a node is dragged. The `size` is in
+    /// graph space, see [`GraphEditorState::node_sizes`].
+    ResizeNode {
+        node: NodeId,
+        size: Vec2,
+    },
+    /// Forgets the size set by the user, so the node fits its contents again.
+    ResetNodeSize(NodeId),
     User(UserResponse),
 }
 
@@ -75,6 +104,75 @@ impl<UserResponse: UserResponseTrait, NodeData: NodeDataTrait> Default
         }
     }
 }
+
+/// The commands the node finder lists when its query starts with `>`, before
+/// the user commands. See [`EditorCommandTrait`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuiltinCommand {
+    ZoomToFit,
+    FrameSelection,
+    SelectAll,
+    SelectUpstream,
+    SelectDownstream,
+    AlignLeft,
+    AlignTop,
+    DeleteSelection,
+}
+
+impl BuiltinCommand {
+    /// All the commands, in the order the node finder lists them.
+    pub const ALL: [BuiltinCommand; 8] = [
+        BuiltinCommand::ZoomToFit,
+        BuiltinCommand::FrameSelection,
+        BuiltinCommand::SelectAll,
+        BuiltinCommand::SelectUpstream,
+        BuiltinCommand::SelectDownstream,
+        BuiltinCommand::AlignLeft,
+        BuiltinCommand::AlignTop,
+        BuiltinCommand::DeleteSelection,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BuiltinCommand::ZoomToFit => "Zoom to fit",
+            BuiltinCommand::FrameSelection => "Frame selection",
+            BuiltinCommand::SelectAll => "Select all",
+            BuiltinCommand::SelectUpstream => "Select upstream",
+            BuiltinCommand::SelectDownstream => "Select downstream",
+            BuiltinCommand::AlignLeft => "Align left",
+            BuiltinCommand::AlignTop => "Align top",
+            BuiltinCommand::DeleteSelection => "Delete selection",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            BuiltinCommand::ZoomToFit => "Shows all the nodes. (Home)",
+            BuiltinCommand::FrameSelection => "Shows the selected nodes. (F)",
+            BuiltinCommand::SelectAll => "Selects all the nodes.",
+            BuiltinCommand::SelectUpstream => {
+                "Adds the nodes the selected nodes depend on to the selection."
+            }
+            BuiltinCommand::SelectDownstream => {
+                "Adds the nodes depending on the selected nodes to the selection."
+            }
+            BuiltinCommand::AlignLeft => "Lines up the left edges of the selected nodes.",
+            BuiltinCommand::AlignTop => "Lines up the top edges of the selected nodes.",
+            BuiltinCommand::DeleteSelection => {
+                "Deletes the selected nodes and connections. (Delete)"
+            }
+        }
+    }
+}
+
+/// Identifies a command listed by the node finder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EditorCommandId {
+    Builtin(BuiltinCommand),
+    /// See [`EditorCommandTrait::command_id`].
+    User(String),
+}
+
 pub struct GraphNodeWidget<
     'a,
     NodeData: NodeDataTrait,
@@ -89,7 +187,14 @@ pub struct GraphNodeWidget<
     pub node_id: NodeId,
     pub ongoing_drag: Option<(NodeId, AnyParameterId)>,
     pub selected: bool,
-    pub pan: egui::Vec2,
+    /// The size set by the user, in graph space. When `None`, the node fits
+    /// its contents, up to [`GraphNodeWidget::MAX_NODE_SIZE`].
+    pub size: Option<Vec2>,
+    /// Only the titlebar is shown, with all the ports stacked on its edges.
+    pub collapsed: bool,
+    /// Where the ports are, see [`NodeOrientation`].
+    pub orientation: NodeOrientation,
+    pub theme: &'a GraphTheme,
 }
 
 impl<NodeData, DataType, ValueType, NodeTemplate, UserResponse, UserState, CategoryType>
@@ -118,11 +223,8 @@ where
     /// Merge another GraphEditorState object into the current one
     /// and return the node ids of any new nodes
     pub fn merge(&mut self, ui: &Ui, other: &mut Self) -> HashSet<NodeId> {
-        other.zoom(ui, self.pan_zoom.zoom / other.pan_zoom.zoom);
-
         let old_to_new_nodes = self.graph.merge(&other.graph);
 
-        let editor_rect = ui.max_rect();
         let cursor_pos = ui
             .ctx()
             .input(|i| i.pointer.hover_pos().unwrap_or(Pos2::ZERO));
@@ -131,8 +233,7 @@ where
         self.selected_nodes.clear();
         for (iteration, (old_node, new_node)) in old_to_new_nodes.iter().enumerate() {
             if iteration == 0 {
-                let first_node_position: Pos2 =
-                    cursor_pos - self.pan_zoom.pan - editor_rect.min.to_vec2();
+                let first_node_position: Pos2 = self.pan_zoom.screen_to_graph(cursor_pos);
                 self.node_positions.insert(*new_node, first_node_position);
                 node_offset = first_node_position
                     - *other.node_positions.get(*old_node).unwrap_or(&Pos2::ZERO);
@@ -144,6 +245,12 @@ where
             }
             self.node_order.push(*new_node);
             self.selected_nodes.insert(*new_node);
+            if other.collapsed_nodes.contains(old_node) {
+                self.collapsed_nodes.insert(*new_node);
+            }
+            if let Some(size) = other.node_sizes.get(*old_node) {
+                self.node_sizes.insert(*new_node, *size);
+            }
         }
 
         old_to_new_nodes.into_values().collect::<HashSet<NodeId>>()
@@ -200,7 +307,7 @@ where
         }
 
         if drag_started_on_background && mouse.primary_down() {
-            self.ongoing_box_selection = Some(cursor_pos);
+            self.ongoing_box_selection = Some(self.pan_zoom.screen_to_graph(cursor_pos));
         }
         if mouse.primary_released() || drag_released_on_background {
             self.ongoing_box_selection = None;
@@ -215,26 +322,136 @@ where
         user_state: &mut UserState,
         prepend_responses: Vec<NodeResponse<UserResponse, NodeData>>,
     ) -> GraphResponse<UserResponse, NodeData> {
+        self.draw_graph_editor_with_commands(
+            ui,
+            all_kinds,
+            Vec::<NoCommands<NodeData>>::new(),
+            user_state,
+            prepend_responses,
+        )
+    }
+
+    /// Like [`GraphEditorState::draw_graph_editor`], with user commands
+    /// listed by the node finder after the [`BuiltinCommand`]s.
+    #[must_use]
+    pub fn draw_graph_editor_with_commands<Command>(
+        &mut self,
+        ui: &mut Ui,
+        all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
+        all_commands: impl EditorCommandIter<Item = Command>,
+        user_state: &mut UserState,
+        prepend_responses: Vec<NodeResponse<UserResponse, NodeData>>,
+    ) -> GraphResponse<UserResponse, NodeData>
+    where
+        Command: EditorCommandTrait<NodeData = NodeData>,
+    {
         let editor_rect = ui.max_rect();
+        self.pan_zoom.editor_rect = editor_rect;
         // Zoom may have never taken place, so ensure we use parent style
         if !self.pan_zoom.started {
             self.zoom(ui, 1.0);
             self.pan_zoom.started = true;
         }
 
-        // Zoom only within area where graph is shown
+        // Zoom and scroll only within area where graph is shown
         if ui.rect_contains_pointer(editor_rect) || self.pan_zoom.enable_zoom_from_out_of_rect {
-            let scroll_delta = ui.input(|i| i.smooth_scroll_delta.y);
-            if scroll_delta != 0.0 {
-                self.zoom(ui, (scroll_delta * 0.002).exp());
+            let (scroll_delta, pinch_delta) = ui.input(|i| (i.smooth_scroll_delta, i.zoom_delta()));
+            match self.pan_zoom.scroll_behavior {
+                ScrollBehavior::Zoom => {
+                    if scroll_delta.y != 0.0 {
+                        self.zoom(ui, (scroll_delta.y * self.pan_zoom.zoom_speed).exp());
+                    }
+                }
+                ScrollBehavior::Pan => {
+                    self.pan_zoom.pan += scroll_delta;
+                }
+            }
+            // Pinch gestures and ctrl + scroll
+            if pinch_delta != 1.0 {
+                self.zoom(ui, pinch_delta);
+            }
+
+            // View commands. Skipped while a text field has focus, so typing
+            // inside the nodes or the node finder doesn't move the view.
+            if !ui.ctx().wants_keyboard_input() {
+                let (fit_pressed, frame_pressed) = ui.input(|i| {
+                    (
+                        i.key_pressed(Key::Home),
+                        i.modifiers.is_none() && i.key_pressed(Key::F),
+                    )
+                });
+                if fit_pressed {
+                    self.zoom_to_fit(ui);
+                } else if frame_pressed {
+                    self.frame_selection(ui);
+                }
             }
         }
 
-        // Render graph zoomed
-        let zoomed_style = self.pan_zoom.zoomed_style.clone();
-        show_zoomed(ui.style().clone(), zoomed_style, ui, |ui| {
-            self.draw_graph_editor_inside_zoom(ui, all_kinds, user_state, prepend_responses)
-        })
+        // The inner ui may live in a transformed layer, where egui can't tell
+        // whether other areas cover the editor. Check it from the outer ui.
+        let cursor_in_editor = ui.rect_contains_pointer(editor_rect);
+
+        // View commands picked in the node finder are run here, where the ui
+        // has the editor rect and the unzoomed style.
+        let (response, view_command) = match self.pan_zoom.render_mode {
+            ZoomRenderMode::Restyle => {
+                // Render graph zoomed
+                let view = self.pan_zoom.clone();
+                let zoomed_style = self.pan_zoom.zoomed_style.clone();
+                show_zoomed(ui.style().clone(), zoomed_style, ui, |ui| {
+                    self.draw_graph_editor_inside_zoom(
+                        ui,
+                        &view,
+                        cursor_in_editor,
+                        all_kinds,
+                        all_commands,
+                        user_state,
+                        prepend_responses,
+                    )
+                })
+            }
+            ZoomRenderMode::LayerTransform => {
+                // Draw the graph unscaled, in graph space, and let egui apply
+                // the camera transform to the whole layer.
+                let to_global = self.pan_zoom.graph_to_screen_transform();
+                let graph_layer_id = LayerId::new(ui.layer_id().order, ui.id().with("graph_layer"));
+                ui.ctx().set_sublayer(ui.layer_id(), graph_layer_id);
+                ui.ctx().set_transform_layer(graph_layer_id, to_global);
+
+                let graph_rect = to_global.inverse() * editor_rect;
+                let mut graph_ui = ui.new_child(
+                    UiBuilder::new()
+                        .layer_id(graph_layer_id)
+                        .max_rect(graph_rect),
+                );
+                graph_ui.set_clip_rect(to_global.inverse() * ui.clip_rect());
+                graph_ui.expand_to_include_rect(to_global.inverse() * ui.min_rect());
+
+                // Inside the layer, graph space and layer space are the same
+                let view = PanZoom {
+                    pan: -graph_rect.min.to_vec2(),
+                    zoom: 1.0,
+                    editor_rect: graph_rect,
+                    ..self.pan_zoom.clone()
+                };
+                self.draw_graph_editor_inside_zoom(
+                    &mut graph_ui,
+                    &view,
+                    cursor_in_editor,
+                    all_kinds,
+                    all_commands,
+                    user_state,
+                    prepend_responses,
+                )
+            }
+        };
+        match view_command {
+            Some(BuiltinCommand::ZoomToFit) => self.zoom_to_fit(ui),
+            Some(BuiltinCommand::FrameSelection) => self.frame_selection(ui),
+            _ => {}
+        }
+        response
     }
 
     /// Reset zoom to 1.0
@@ -244,46 +461,347 @@ where
     }
 
     /// Zoom within the where you call `draw_graph_editor`. Use values like 1.01, or 0.99 to zoom.
-    /// For example: `let zoom_delta = (scroll_delta * 0.002).exp();`
+    /// For example: `let zoom_delta = (scroll_delta * pan_zoom.zoom_speed).exp();`
+    /// The result is clamped to the `min_zoom` and `max_zoom` of the [`PanZoom`].
     pub fn zoom(&mut self, ui: &Ui, zoom_delta: f32) {
+        // Zoom towards the cursor: the graph position under it must not move
+        let towards = ui
+            .ctx()
+            .input(|i| i.pointer.hover_pos())
+            .unwrap_or(self.pan_zoom.editor_rect.center());
+        let graph_pos = self.pan_zoom.screen_to_graph(towards);
         // Update zoom, and styles
-        let zoom_before = self.pan_zoom.zoom;
         self.pan_zoom.zoom(ui.style(), zoom_delta);
-        if zoom_before != self.pan_zoom.zoom {
-            let actual_delta = self.pan_zoom.zoom / zoom_before;
-            let editor_rect: Rect = ui.max_rect();
-            self.update_node_positions_after_zoom(
-                actual_delta,
-                ui.ctx().input(|i| {
-                    i.pointer
-                        .hover_pos()
-                        .unwrap_or(editor_rect.size().to_pos2() * 0.5)
-                        - editor_rect.min
-                }),
-            );
+        self.pan_zoom.pan += towards - self.pan_zoom.graph_to_screen(graph_pos);
+    }
+
+    /// Zooms and pans the view so that all the nodes in the graph fit inside
+    /// the editor rect. Bound to the `Home` key. The `ui` should be the same
+    /// one passed to `draw_graph_editor`.
+    pub fn zoom_to_fit(&mut self, ui: &Ui) {
+        let nodes: Vec<NodeId> = self.graph.iter_nodes().collect();
+        self.frame_nodes(ui, nodes);
+    }
+
+    /// Zooms and pans the view so that the selected nodes fit inside the
+    /// editor rect. Does nothing when no node is selected. Bound to the `F`
+    /// key.
+    pub fn frame_selection(&mut self, ui: &Ui) {
+        let nodes: Vec<NodeId> = self.selected_nodes.iter().copied().collect();
+        self.frame_nodes(ui, nodes);
+    }
+
+    /// Selects all the nodes.
+    pub fn select_all(&mut self) {
+        self.selected_nodes = self.graph.iter_nodes().collect();
+    }
+
+    /// Adds the nodes the selected nodes depend on, directly or not, to the
+    /// selection.
+    pub fn select_upstream(&mut self) {
+        self.extend_selection(|graph, input, output| {
+            (graph.get_input(input).node, graph.get_output(output).node)
+        });
+    }
+
+    /// Adds the nodes depending on the selected nodes, directly or not, to
+    /// the selection.
+    pub fn select_downstream(&mut self) {
+        self.extend_selection(|graph, input, output| {
+            (graph.get_output(output).node, graph.get_input(input).node)
+        });
+    }
+
+    /// Adds the nodes at the other end of the connections to the selection,
+    /// until there are no more. `ends` returns the node a connection goes
+    /// from, followed by the node it leads to.
+    fn extend_selection(
+        &mut self,
+        ends: impl Fn(
+            &Graph<NodeData, DataType, ValueType, UserState>,
+            InputId,
+            OutputId,
+        ) -> (NodeId, NodeId),
+    ) {
+        loop {
+            let added: Vec<NodeId> = self
+                .graph
+                .iter_connections()
+                .map(|(input, output)| ends(&self.graph, input, output))
+                .filter(|(from, to)| {
+                    self.selected_nodes.contains(from) && !self.selected_nodes.contains(to)
+                })
+                .map(|(_, to)| to)
+                .collect();
+            if added.is_empty() {
+                break;
+            }
+            self.selected_nodes.extend(added);
         }
     }
 
-    fn update_node_positions_after_zoom(&mut self, zoom_delta: f32, towards: Vec2) {
-        // Update node positions, zoom towards the specified screen position
-        for (_id, node_pos) in self.node_positions.iter_mut() {
-            // 1. Get node local position (relative to origo)
-            let local_pos = *node_pos - towards + self.pan_zoom.pan;
-            // 2. Scale local position by zoom delta
-            let scaled_local_pos = local_pos * zoom_delta;
-            // 3. Transform back to global position
-            *node_pos = scaled_local_pos + towards - self.pan_zoom.pan;
-            // This way we can retain pan untouched when zooming :)
+    /// Moves the selected nodes so their left edges line up with the
+    /// leftmost one.
+    pub fn align_selection_left(&mut self) {
+        self.align_selection(|pos| &mut pos.x);
+    }
+
+    /// Moves the selected nodes so their top edges line up with the topmost
+    /// one.
+    pub fn align_selection_top(&mut self) {
+        self.align_selection(|pos| &mut pos.y);
+    }
+
+    fn align_selection(&mut self, coordinate: impl Fn(&mut Pos2) -> &mut f32) {
+        let target = self
+            .selected_nodes
+            .iter()
+            .filter_map(|node_id| self.node_positions.get(*node_id).copied())
+            .map(|mut pos| *coordinate(&mut pos))
+            .reduce(f32::min);
+        if let Some(target) = target {
+            for node_id in &self.selected_nodes {
+                if let Some(pos) = self.node_positions.get_mut(*node_id) {
+                    *coordinate(pos) = target;
+                }
+            }
+        }
+    }
+
+    /// Runs a command picked in the node finder, returning the responses to
+    /// handle like the ones of the nodes. The view commands need the outer ui,
+    /// so they are returned for the caller to run instead. A user command that
+    /// is no longer listed by `all_commands` does nothing.
+    #[allow(clippy::type_complexity)]
+    fn run_command<Command>(
+        &mut self,
+        id: &EditorCommandId,
+        all_commands: &impl EditorCommandIter<Item = Command>,
+        user_state: &mut UserState,
+        extra_responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+    ) -> (
+        Vec<NodeResponse<UserResponse, NodeData>>,
+        Option<BuiltinCommand>,
+    )
+    where
+        Command: EditorCommandTrait<NodeData = NodeData>,
+    {
+        let mut responses = Vec::new();
+        match id {
+            EditorCommandId::Builtin(
+                command @ (BuiltinCommand::ZoomToFit | BuiltinCommand::FrameSelection),
+            ) => return (responses, Some(*command)),
+            EditorCommandId::Builtin(BuiltinCommand::SelectAll) => self.select_all(),
+            EditorCommandId::Builtin(BuiltinCommand::SelectUpstream) => self.select_upstream(),
+            EditorCommandId::Builtin(BuiltinCommand::SelectDownstream) => self.select_downstream(),
+            EditorCommandId::Builtin(BuiltinCommand::AlignLeft) => self.align_selection_left(),
+            EditorCommandId::Builtin(BuiltinCommand::AlignTop) => self.align_selection_top(),
+            EditorCommandId::Builtin(BuiltinCommand::DeleteSelection) => {
+                responses = self.delete_selection(user_state, extra_responses);
+            }
+            EditorCommandId::User(id) => {
+                let command = all_commands
+                    .all_commands()
+                    .into_iter()
+                    .find(|command| command.command_id(user_state) == id.as_str());
+                if let Some(command) = command {
+                    responses = command.run(&mut self.graph, &mut self.selected_nodes, user_state);
+                }
+            }
+        }
+        (responses, None)
+    }
+
+    /// Whether `action` can be chosen in the context menu of the node.
+    fn node_menu_enabled(
+        &self,
+        action: NodeMenuAction,
+        node_id: NodeId,
+        user_state: &mut UserState,
+    ) -> bool {
+        match action {
+            NodeMenuAction::Delete => {
+                self.graph[node_id]
+                    .user_data
+                    .can_delete(node_id, &self.graph, user_state)
+            }
+            NodeMenuAction::ResetSize => self.node_sizes.contains_key(node_id),
+            _ => true,
+        }
+    }
+
+    /// Returns a `DeleteNodeUi` response for each selected node that can be
+    /// deleted, and removes the selected connections right away, reporting
+    /// them in `extra_responses`. A `DisconnectEvent` handled with the other
+    /// responses would start dragging the connection.
+    fn delete_selection(
+        &mut self,
+        user_state: &mut UserState,
+        extra_responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let mut responses = Vec::new();
+        for node_id in self.selected_nodes.iter() {
+            if self.graph.nodes[*node_id]
+                .user_data
+                .can_delete(*node_id, &self.graph, user_state)
+            {
+                responses.push(NodeResponse::DeleteNodeUi(*node_id));
+            }
+        }
+        for input in self.selected_wires.drain() {
+            if let Some(output) = self.graph.connection(input) {
+                self.graph.remove_connection(input);
+                extra_responses.push(NodeResponse::DisconnectEvent { output, input });
+            }
+        }
+        responses
+    }
+
+    fn frame_nodes(&mut self, ui: &Ui, nodes: Vec<NodeId>) {
+        // Nodes that were never drawn don't have a rect yet, so their position
+        // is the best we can do.
+        let mut bounds = Rect::NOTHING;
+        for node_id in nodes {
+            if let Some(rect) = self.node_rects.get(&node_id) {
+                bounds = bounds.union(*rect);
+            } else if let Some(pos) = self.node_positions.get(node_id) {
+                bounds.extend_with(*pos);
+            }
+        }
+        if !bounds.is_finite() {
+            return;
         }
+
+        let editor_rect = ui.max_rect();
+        let available =
+            (editor_rect.size() - Vec2::splat(2.0 * FRAME_PADDING)).max(Vec2::splat(1.0));
+        let target_zoom = (available.x / bounds.width()).min(available.y / bounds.height());
+        self.pan_zoom
+            .zoom(ui.style(), target_zoom / self.pan_zoom.zoom);
+
+        // Center the bounds in the editor rect
+        self.pan_zoom.pan =
+            editor_rect.center() - editor_rect.min - bounds.center().to_vec2() * self.pan_zoom.zoom;
     }
 
-    fn draw_graph_editor_inside_zoom(
+    /// Adds a node built from `node_kind` at `position`, in graph space.
+    /// Returns the id of the new node.
+    fn create_node(
+        &mut self,
+        node_kind: &NodeTemplate,
+        position: Pos2,
+        user_state: &mut UserState,
+    ) -> NodeId {
+        let new_node = self.graph.add_node(
+            node_kind.node_graph_label(user_state),
+            node_kind.user_data(user_state),
+        );
+        node_kind.build_node(&mut self.graph, user_state, new_node);
+        self.node_positions.insert(new_node, position);
+        self.node_order.push(new_node);
+        new_node
+    }
+
+    /// Takes the node out of the chain it's part of: the first of its inputs
+    /// that is connected, and whose type matches some of its connected outputs,
+    /// is bridged to the inputs downstream of those outputs. Returns the
+    /// responses describing the changes.
+    fn detach_node(&mut self, node_id: NodeId) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let mut responses = Vec::new();
+        let inputs: Vec<InputId> = self.graph[node_id].input_ids().collect();
+        for input in inputs {
+            let Some(upstream) = self.graph.connection(input) else {
+                continue;
+            };
+            let typ = &self.graph.get_output(upstream).typ;
+            let downstream: Vec<(InputId, OutputId)> = self
+                .graph
+                .iter_connections()
+                .filter(|(_, output)| {
+                    let output = self.graph.get_output(*output);
+                    output.node == node_id && output.typ == *typ
+                })
+                .collect();
+            if downstream.is_empty() {
+                continue;
+            }
+
+            self.graph.remove_connection(input);
+            responses.push(NodeResponse::DisconnectEvent {
+                output: upstream,
+                input,
+            });
+            for (downstream_input, output) in downstream {
+                self.graph.remove_connection(downstream_input);
+                self.graph.add_connection(upstream, downstream_input);
+                responses.push(NodeResponse::DisconnectEvent {
+                    output,
+                    input: downstream_input,
+                });
+                responses.push(NodeResponse::ConnectEventEnded {
+                    output: upstream,
+                    input: downstream_input,
+                });
+            }
+            break;
+        }
+        responses
+    }
+
+    /// Pans the view when the cursor gets within `auto_pan_margin` of the
+    /// editor edges, faster the closer it gets. Returns the applied screen
+    /// space pan delta.
+    fn auto_pan(&mut self, ui: &Ui, screen_cursor_pos: Pos2) -> Vec2 {
+        let margin = self.pan_zoom.auto_pan_margin;
+        if margin <= 0.0 {
+            return Vec2::ZERO;
+        }
+        let inner_rect = self.pan_zoom.editor_rect.shrink(margin);
+        let edge_closeness = |pos: f32, min: f32, max: f32| {
+            if pos < min {
+                ((min - pos) / margin).min(1.0)
+            } else if pos > max {
+                -((pos - max) / margin).min(1.0)
+            } else {
+                0.0
+            }
+        };
+        let direction = vec2(
+            edge_closeness(screen_cursor_pos.x, inner_rect.left(), inner_rect.right()),
+            edge_closeness(screen_cursor_pos.y, inner_rect.top(), inner_rect.bottom()),
+        );
+        if direction == Vec2::ZERO {
+            return Vec2::ZERO;
+        }
+
+        let dt = ui.input(|i| i.stable_dt).min(0.1);
+        let pan_delta = direction * self.pan_zoom.auto_pan_speed * dt;
+        self.pan_zoom.pan += pan_delta;
+        ui.ctx().request_repaint();
+        pan_delta
+    }
+
+    /// Draws the graph inside `ui`. The `view` maps graph space into the
+    /// coordinate system of `ui`'s layer, which is the screen unless the graph
+    /// is rendered with [`ZoomRenderMode::LayerTransform`]. Returns the view
+    /// command picked in the node finder, if any, for the caller to run.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_graph_editor_inside_zoom<Command>(
         &mut self,
         ui: &mut Ui,
+        view: &PanZoom,
+        mut cursor_in_editor: bool,
         all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
+        all_commands: impl EditorCommandIter<Item = Command>,
         user_state: &mut UserState,
         prepend_responses: Vec<NodeResponse<UserResponse, NodeData>>,
-    ) -> GraphResponse<UserResponse, NodeData> {
+    ) -> (
+        GraphResponse<UserResponse, NodeData>,
+        Option<BuiltinCommand>,
+    )
+    where
+        Command: EditorCommandTrait<NodeData = NodeData>,
+    {
         debug_assert_eq!(
             self.node_order.iter().copied().collect::<HashSet<_>>(),
             self.graph.iter_nodes().collect::<HashSet<_>>(),
@@ -291,17 +809,19 @@ where
         inconsistent self. It has either more or less values than the graph."
         );
 
-        // This causes the graph editor to use as much free space as it can.
-        // (so for windows it will use up to the resizeably set limit
-        // and for a Panel it will fill it completely)
-        let editor_rect = ui.max_rect();
-
-        let cursor_pos = ui
+        // The node finder is drawn in its own area, outside of the graph
+        // layer, so it works with screen positions.
+        let screen_cursor_pos = ui
             .ctx()
             .input(|i| i.pointer.hover_pos().unwrap_or(Pos2::ZERO));
-        let mut cursor_in_editor = ui.rect_contains_pointer(editor_rect);
+        let cursor_pos = local_pointer_pos(ui).unwrap_or(Pos2::ZERO);
         let mut cursor_in_finder = false;
 
+        let theme = self
+            .theme
+            .clone()
+            .unwrap_or_else(|| GraphTheme::from_visuals(ui.visuals()));
+
         // Gets filled with the node metrics as they are drawn
         let mut port_locations = PortLocations::new();
         let mut node_rects = NodeRects::new();
@@ -310,6 +830,10 @@ where
         // executed at the end of this function.
         let mut delayed_responses: Vec<NodeResponse<UserResponse, NodeData>> = prepend_responses;
 
+        // Some responses generate additional responses when processed. These
+        // are stored here to report them back to the user.
+        let mut extra_responses: Vec<NodeResponse<UserResponse, NodeData>> = Vec::new();
+
         // Used to detect when the background was clicked
         let mut click_on_background = false;
 
@@ -334,21 +858,21 @@ where
             .ctx()
             .input(|i| i.modifiers.matches_logically(Modifiers::SHIFT));
 
-        // Delete selected nodes with the delete key
-        if ui.ctx().input(|i| i.key_pressed(Key::Delete)) {
-            for node_id in self.selected_nodes.iter() {
-                if self.graph.nodes[*node_id].user_data.can_delete(
-                    *node_id,
-                    &self.graph,
-                    user_state,
-                ) {
-                    delayed_responses.push(NodeResponse::DeleteNodeUi(*node_id));
-                }
-            }
+        // Delete selected nodes with the delete key, unless it's meant for a
+        // text field
+        if !ui.ctx().wants_keyboard_input() && ui.ctx().input(|i| i.key_pressed(Key::Delete)) {
+            let responses = self.delete_selection(user_state, &mut extra_responses);
+            delayed_responses.extend(responses);
         }
 
         /* Draw nodes */
+        let mut node_orientations = HashMap::new();
         for node_id in self.node_order.iter().copied() {
+            let orientation = self.graph[node_id]
+                .user_data
+                .orientation(node_id, &self.graph, user_state)
+                .unwrap_or(self.node_orientation);
+            node_orientations.insert(node_id, orientation);
             let responses = GraphNodeWidget {
                 position: self.node_positions.get_mut(node_id).unwrap(),
                 graph: &mut self.graph,
@@ -360,56 +884,203 @@ where
                     .selected_nodes
                     .iter()
                     .any(|selected| *selected == node_id),
-                pan: self.pan_zoom.pan + editor_rect.min.to_vec2(),
+                size: self.node_sizes.get(node_id).copied(),
+                collapsed: self.collapsed_nodes.contains(&node_id),
+                orientation,
+                theme: &theme,
             }
-            .show(&self.pan_zoom, ui, user_state);
+            .show(view, ui, user_state);
 
             // Actions executed later
             delayed_responses.extend(responses);
         }
 
+        // Remember the node rects in graph space, for view commands and
+        // wire routing
+        self.node_rects = node_rects
+            .iter()
+            .map(|(node_id, rect)| (*node_id, view.screen_to_graph_rect(*rect)))
+            .collect();
+
         /* Draw the node finder, if open */
+        let mut picked_kind = None;
+        let mut picked_command = None;
         if let Some(ref mut node_finder) = self.node_finder {
             let mut node_finder_area = Area::new(Id::new("node_finder")).order(Order::Foreground);
             if let Some(pos) = node_finder.position {
                 node_finder_area = node_finder_area.current_pos(pos);
             }
+            // The templates are gathered once, when the finder opens. Only
+            // list the ones that can connect to the dragged port.
+            let graph = &self.graph;
+            let connect_from = node_finder.connect_from;
+            let templates = |user_state: &mut UserState| {
+                let mut kinds = all_kinds.all_kinds();
+                if let Some(port) = connect_from {
+                    let port_type = graph.any_param_type(port).unwrap();
+                    kinds.retain(|kind| {
+                        let signature = kind.node_signature(user_state);
+                        match port {
+                            AnyParameterId::Output(_) => signature.accepts(port_type),
+                            AnyParameterId::Input(_) => signature.produces(port_type),
+                        }
+                    });
+                }
+                kinds
+            };
+            let commands = |user_state: &mut UserState| {
+                BuiltinCommand::ALL
+                    .iter()
+                    .map(|command| FinderCommand {
+                        id: EditorCommandId::Builtin(*command),
+                        label: command.label().to_owned(),
+                        description: Some(command.description().to_owned()),
+                    })
+                    .chain(
+                        all_commands
+                            .all_commands()
+                            .iter()
+                            .map(|command| FinderCommand {
+                                id: EditorCommandId::User(
+                                    command.command_id(user_state).into_owned(),
+                                ),
+                                label: command.label(user_state).into_owned(),
+                                description: command.description(user_state),
+                            }),
+                    )
+                    .collect()
+            };
             node_finder_area.show(ui.ctx(), |ui| {
-                if let Some(node_kind) = node_finder.show(ui, all_kinds, user_state) {
-                    let new_node = self.graph.add_node(
-                        node_kind.node_graph_label(user_state),
-                        node_kind.user_data(user_state),
-                    );
-                    node_kind.build_node(&mut self.graph, user_state, new_node);
-                    self.node_positions.insert(
-                        new_node,
-                        node_finder.position.unwrap_or(cursor_pos)
-                            - self.pan_zoom.pan
-                            - editor_rect.min.to_vec2(),
-                    );
-                    self.node_order.push(new_node);
-
-                    delayed_responses.push(NodeResponse::CreatedNode(new_node));
-
-                    should_close_node_finder = true;
+                let choice = node_finder.show(
+                    ui,
+                    templates,
+                    commands,
+                    user_state,
+                    &theme,
+                    &mut self.node_finder_memory,
+                );
+                match choice {
+                    Some(NodeFinderChoice::Template(node_kind)) => {
+                        let pos = node_finder.position.unwrap_or(screen_cursor_pos);
+                        picked_kind = Some((node_kind, pos, node_finder.connect_from));
+                        should_close_node_finder = true;
+                    }
+                    Some(NodeFinderChoice::Command(id)) => {
+                        picked_command = Some(id);
+                        should_close_node_finder = true;
+                    }
+                    None => {}
                 }
                 let finder_rect = ui.min_rect();
                 // If the cursor is not in the main editor, check if the cursor is in the finder
                 // if the cursor is in the finder, then we can consider that also in the editor.
-                if finder_rect.contains(cursor_pos) {
+                if finder_rect.contains(screen_cursor_pos) {
                     cursor_in_editor = true;
                     cursor_in_finder = true;
                 }
             });
         }
+        /* Create the template dropped from the node palette, if any */
+        let pointer_released = ui.input(|i| i.pointer.any_released());
+        if pointer_released && cursor_in_editor && !cursor_in_finder {
+            if let Some(payload) = DragAndDrop::take_payload::<NodePalettePayload>(ui.ctx()) {
+                let node_kind = all_kinds
+                    .all_kinds()
+                    .into_iter()
+                    .find(|kind| kind.node_template_id(user_state) == payload.0);
+                if let Some(node_kind) = node_kind {
+                    picked_kind.get_or_insert((node_kind, screen_cursor_pos, None));
+                }
+            }
+        }
+        if let Some((node_kind, pos, connect_from)) = picked_kind {
+            let position = self.pan_zoom.screen_to_graph(pos);
+            let new_node = self.create_node(&node_kind, position, user_state);
+            delayed_responses.push(NodeResponse::CreatedNode(new_node));
+
+            // Complete the connection that opened the finder
+            if let Some(port) = connect_from {
+                let port_type = self.graph.any_param_type(port).unwrap();
+                match port {
+                    AnyParameterId::Output(output) => {
+                        if let Some(input) = self.graph.first_free_input(new_node, port_type) {
+                            delayed_responses
+                                .push(NodeResponse::ConnectEventEnded { output, input });
+                        }
+                    }
+                    AnyParameterId::Input(input) => {
+                        let output = self.graph[new_node]
+                            .output_ids()
+                            .find(|output| self.graph.get_output(*output).typ == *port_type);
+                        if let Some(output) = output {
+                            delayed_responses
+                                .push(NodeResponse::ConnectEventEnded { output, input });
+                        }
+                    }
+                }
+            }
+        }
+        let mut view_command = None;
+        if let Some(id) = picked_command {
+            let (responses, command) =
+                self.run_command(&id, &all_commands, user_state, &mut extra_responses);
+            delayed_responses.extend(responses);
+            view_command = command;
+        }
         if should_close_node_finder {
             self.node_finder = None;
         }
 
+        let find_node = |id: Option<Id>| {
+            id.and_then(|id| {
+                self.node_order
+                    .iter()
+                    .copied()
+                    .find(|node_id| node_window_id(*node_id) == id)
+            })
+        };
+        let dragged_node = find_node(ui.ctx().dragged_id());
+        let drag_started_node = find_node(ui.ctx().drag_started_id());
+        let drag_stopped_node = find_node(ui.ctx().drag_stopped_id());
+        let alt_pressed = ui.input(|i| i.modifiers.alt);
+
+        // Alt + dragging a node takes it out of the chain it's part of
+        if let Some(node_id) = drag_started_node {
+            if alt_pressed {
+                extra_responses.extend(self.detach_node(node_id));
+            }
+        }
+
+        /* Knife gesture: Ctrl + right drag cuts the connections it crosses */
+        let (secondary_pressed, secondary_down, secondary_released, command) = ui.input(|i| {
+            (
+                i.pointer.secondary_pressed(),
+                i.pointer.secondary_down(),
+                i.pointer.secondary_released(),
+                i.modifiers.command,
+            )
+        });
+        if secondary_pressed && command && cursor_in_editor && !cursor_in_finder {
+            self.ongoing_knife = Some(vec![view.screen_to_graph(cursor_pos)]);
+            self.node_finder = None;
+            self.wire_context_menu = None;
+            self.node_context_menu = None;
+        }
+        if let Some(knife) = &mut self.ongoing_knife {
+            let pos = view.screen_to_graph(cursor_pos);
+            if knife.last().is_none_or(|last| last.distance(pos) > 2.0) {
+                knife.push(pos);
+            }
+        }
+        let knife_points: Option<Vec<Pos2>> = self
+            .ongoing_knife
+            .as_ref()
+            .map(|knife| knife.iter().map(|pos| view.graph_to_screen(*pos)).collect());
+        let knife_released = knife_points.is_some() && (secondary_released || !secondary_down);
+
         /* Draw connections */
-        if let Some((_, ref locator)) = self.connection_in_progress {
+        if let Some((start_node, ref locator)) = self.connection_in_progress {
             let port_type = self.graph.any_param_type(*locator).unwrap();
-            let connection_color = port_type.data_type_color(user_state);
             let start_pos = port_locations[locator];
 
             // Find a port to connect to
@@ -457,7 +1128,7 @@ where
                 AnyParameterId::Output(_) => (
                     start_pos,
                     snap_to_ports(
-                        &self.pan_zoom,
+                        view,
                         &self.graph,
                         port_type,
                         &self.graph.inputs,
@@ -467,7 +1138,7 @@ where
                 ),
                 AnyParameterId::Input(_) => (
                     snap_to_ports(
-                        &self.pan_zoom,
+                        view,
                         &self.graph,
                         port_type,
                         &self.graph.outputs,
@@ -477,37 +1148,290 @@ where
                     start_pos,
                 ),
             };
+            // Whatever port it snaps to, the wire is shaped for the node it
+            // started from
+            let orientation = node_orientations[&start_node];
+            let points = wire_points(
+                self.wire_style,
+                &mut self.wire_routes,
+                &self.node_rects,
+                view,
+                None,
+                src_pos,
+                dst_pos,
+                [orientation; 2],
+            );
             draw_connection(
-                &self.pan_zoom,
+                view,
+                &theme,
                 ui.painter(),
+                points,
+                port_type,
+                user_state,
+                None,
+            );
+        }
+
+        let mut wires = Vec::new();
+        for (input, output) in self.graph.iter_connections() {
+            let src_pos = port_locations[&AnyParameterId::Output(output)];
+            let dst_pos = port_locations[&AnyParameterId::Input(input)];
+            let orientations = [
+                node_orientations[&self.graph.get_output(output).node],
+                node_orientations[&self.graph.get_input(input).node],
+            ];
+            let points = wire_points(
+                self.wire_style,
+                &mut self.wire_routes,
+                &self.node_rects,
+                view,
+                Some(input),
                 src_pos,
                 dst_pos,
-                connection_color,
+                orientations,
             );
+            wires.push((input, output, points));
         }
+        self.wire_routes
+            .retain(|input| self.graph.connections.contains_key(input));
+        self.selected_wires
+            .retain(|input| self.graph.connections.contains_key(*input));
+
+        // Only the background can be hovered through to the wires, so nodes
+        // and their widgets keep priority over them.
+        let hovered_wire = if r.hovered()
+            && self.connection_in_progress.is_none()
+            && self.ongoing_box_selection.is_none()
+            && self.ongoing_knife.is_none()
+        {
+            wires
+                .iter()
+                .filter_map(|(input, output, points)| {
+                    // Thick wires are easier to hover
+                    let width = self
+                        .graph
+                        .get_output(*output)
+                        .typ
+                        .wire_width(user_state)
+                        .unwrap_or(theme.wire_width);
+                    let max_distance = (width / 2.0 + WIRE_HOVER_DISTANCE) * view.zoom;
+                    let distance = distance_to_polyline(points, cursor_pos);
+                    (distance <= max_distance).then_some((*input, *output, distance))
+                })
+                .min_by(|a, b| a.2.total_cmp(&b.2))
+                .map(|(input, output, _)| (input, output))
+        } else {
+            None
+        };
 
-        for (input, output) in self.graph.iter_connections() {
+        // A node dragged over a connection can be inserted into it, if it has
+        // free ports of the connection's type.
+        let insert_target = dragged_node
+            .or(drag_stopped_node)
+            .filter(|_| !alt_pressed)
+            .and_then(|node_id| {
+                let node_rect = *node_rects.get(&node_id)?;
+                wires.iter().find_map(|(input, output, points)| {
+                    let typ = &self.graph.get_output(*output).typ;
+                    let connects_node = self.graph.get_input(*input).node == node_id
+                        || self.graph.get_output(*output).node == node_id;
+                    if connects_node || !polyline_intersects_rect(points, node_rect) {
+                        return None;
+                    }
+                    let new_input = self.graph.first_free_input(node_id, typ)?;
+                    let new_output = self.graph.first_free_output(node_id, typ)?;
+                    Some((*input, *output, new_input, new_output))
+                })
+            });
+
+        let mut cut_wires = Vec::new();
+        for (input, output, points) in wires {
             let port_type = self
                 .graph
                 .any_param_type(AnyParameterId::Output(output))
                 .unwrap();
-            let connection_color = port_type.data_type_color(user_state);
-            let src_pos = port_locations[&AnyParameterId::Output(output)];
-            let dst_pos = port_locations[&AnyParameterId::Input(input)];
+            let cut = knife_points
+                .as_ref()
+                .is_some_and(|knife| polylines_intersect(knife, &points));
+            if cut {
+                cut_wires.push((input, output));
+            }
+            let halo = if cut {
+                Some(theme.knife)
+            } else if self.selected_wires.contains(&input) {
+                Some(theme.wire_selected)
+            } else if hovered_wire.is_some_and(|(hovered, _)| hovered == input)
+                || insert_target.is_some_and(|(target, ..)| target == input)
+            {
+                Some(theme.wire_hovered)
+            } else {
+                None
+            };
             draw_connection(
-                &self.pan_zoom,
+                view,
+                &theme,
                 ui.painter(),
-                src_pos,
-                dst_pos,
-                connection_color,
+                points,
+                port_type,
+                user_state,
+                halo,
             );
         }
 
-        /* Handle responses from drawing nodes */
+        if let Some((input, output, new_input, new_output)) = insert_target {
+            if drag_stopped_node.is_some() {
+                self.graph.remove_connection(input);
+                self.graph.add_connection(output, new_input);
+                self.graph.add_connection(new_output, input);
+                extra_responses.push(NodeResponse::DisconnectEvent { output, input });
+                extra_responses.push(NodeResponse::ConnectEventEnded {
+                    output,
+                    input: new_input,
+                });
+                extra_responses.push(NodeResponse::ConnectEventEnded {
+                    output: new_output,
+                    input,
+                });
+            }
+        }
 
-        // Some responses generate additional responses when processed. These
-        // are stored here to report them back to the user.
-        let mut extra_responses: Vec<NodeResponse<UserResponse, NodeData>> = Vec::new();
+        if let Some(knife) = knife_points {
+            if knife_released {
+                // All the cuts are applied in the same frame
+                for (input, output) in cut_wires {
+                    self.graph.remove_connection(input);
+                    extra_responses.push(NodeResponse::DisconnectEvent { output, input });
+                }
+                self.ongoing_knife = None;
+            } else {
+                let stroke = Stroke::new(2.0 * view.zoom, theme.knife);
+                draw_wire(
+                    ui.painter(),
+                    knife,
+                    stroke,
+                    Some((6.0 * view.zoom, 4.0 * view.zoom)),
+                );
+            }
+        }
+
+        // Clicking a wire selects it instead of clearing the selection
+        if let Some((input, output)) = hovered_wire {
+            ui.output_mut(|output| output.cursor_icon = egui::CursorIcon::PointingHand);
+            if click_on_background {
+                click_on_background = false;
+                if !shift_pressed {
+                    self.selected_nodes.clear();
+                    self.selected_wires.clear();
+                }
+                self.selected_wires.insert(input);
+                self.node_finder = None;
+                self.wire_context_menu = None;
+                self.node_context_menu = None;
+                delayed_responses.push(NodeResponse::WireClicked { output, input });
+            }
+        }
+
+        /* Draw the wire context menu, if open */
+        if let Some((input, pos)) = self.wire_context_menu {
+            let mut close_menu = false;
+            let menu = Area::new(Id::new("wire_context_menu"))
+                .order(Order::Foreground)
+                .current_pos(pos)
+                .show(ui.ctx(), |ui| {
+                    Frame::menu(ui.style()).show(ui, |ui| {
+                        if ui.button("Disconnect").clicked() {
+                            if let Some(output) = self.graph.connection(input) {
+                                self.graph.remove_connection(input);
+                                extra_responses
+                                    .push(NodeResponse::DisconnectEvent { output, input });
+                            }
+                            close_menu = true;
+                        }
+                        if ui.button("Select").clicked() {
+                            if !shift_pressed {
+                                self.selected_nodes.clear();
+                                self.selected_wires.clear();
+                            }
+                            self.selected_wires.insert(input);
+                            close_menu = true;
+                        }
+                    });
+                });
+            if menu.response.rect.contains(screen_cursor_pos) {
+                cursor_in_editor = true;
+                cursor_in_finder = true;
+            }
+            if close_menu
+                || menu.response.clicked_elsewhere()
+                || !self.graph.connections.contains_key(input)
+            {
+                self.wire_context_menu = None;
+            }
+        }
+
+        /* Draw the node context menu, if open */
+        if let Some((node_id, pos)) = self.node_context_menu {
+            let mut close_menu = false;
+            let menu = Area::new(Id::new("node_context_menu"))
+                .order(Order::Foreground)
+                .current_pos(pos)
+                .show(ui.ctx(), |ui| {
+                    Frame::menu(ui.style()).show(ui, |ui| {
+                        if !self.graph.nodes.contains_key(node_id) {
+                            return;
+                        }
+                        let collapsed = self.collapsed_nodes.contains(&node_id);
+                        for action in NodeMenuAction::ALL {
+                            let enabled = self.node_menu_enabled(action, node_id, user_state);
+                            let button = Button::new(action.label(collapsed));
+                            if ui.add_enabled(enabled, button).clicked() {
+                                delayed_responses.push(action.response(node_id));
+                                close_menu = true;
+                            }
+                        }
+                        let user_responses = self.graph[node_id].user_data.context_menu_ui(
+                            ui,
+                            node_id,
+                            &self.graph,
+                            user_state,
+                        );
+                        if !user_responses.is_empty() {
+                            delayed_responses.extend(user_responses);
+                            close_menu = true;
+                        }
+                    });
+                });
+            if menu.response.rect.contains(screen_cursor_pos) {
+                cursor_in_editor = true;
+                cursor_in_finder = true;
+            }
+            if close_menu
+                || menu.response.clicked_elsewhere()
+                || !self.graph.nodes.contains_key(node_id)
+            {
+                self.node_context_menu = None;
+            }
+        }
+
+        /* Scroll the view when dragging close to the edges */
+        if dragged_node.is_some()
+            || self.connection_in_progress.is_some()
+            || self.ongoing_box_selection.is_some()
+            || self.ongoing_knife.is_some()
+        {
+            let pan_delta = self.auto_pan(ui, screen_cursor_pos);
+            // The dragged nodes must stay under the cursor while the view moves
+            if let Some(node) = dragged_node {
+                if pan_delta != Vec2::ZERO {
+                    delayed_responses.push(NodeResponse::MoveNode {
+                        node,
+                        drag_delta: -pan_delta / self.pan_zoom.zoom,
+                    });
+                }
+            }
+        }
+
+        /* Handle responses from drawing nodes */
 
         for response in delayed_responses.iter() {
             match response {
@@ -517,12 +1441,13 @@ where
                 NodeResponse::ConnectEventEnded { input, output } => {
                     self.graph.add_connection(*output, *input)
                 }
-                NodeResponse::CreatedNode(_) => {
+                NodeResponse::CreatedNode(_) | NodeResponse::WireClicked { .. } => {
                     //Convenience NodeResponse for users
                 }
                 NodeResponse::SelectNode(node_id) => {
                     if !shift_pressed {
                         self.selected_nodes.clear();
+                        self.selected_wires.clear();
                     }
                     self.selected_nodes.insert(*node_id);
                 }
@@ -542,15 +1467,51 @@ where
                         node,
                     });
                     self.node_positions.remove(*node_id);
+                    self.collapsed_nodes.remove(node_id);
+                    self.node_sizes.remove(*node_id);
                     // Make sure to not leave references to old nodes hanging
                     self.selected_nodes.retain(|id| *id != *node_id);
                     self.node_order.retain(|id| *id != *node_id);
                 }
-                NodeResponse::DisconnectEvent { input, output } => {
-                    let other_node = self.graph.get_output(*output).node;
-                    self.graph.remove_connection(*input);
-                    self.connection_in_progress =
-                        Some((other_node, AnyParameterId::Output(*output)));
+                NodeResponse::DisconnectEvent { input, output } => {
+                    let other_node = self.graph.get_output(*output).node;
+                    self.graph.remove_connection(*input);
+                    self.connection_in_progress =
+                        Some((other_node, AnyParameterId::Output(*output)));
+                }
+                NodeResponse::DuplicateNode(node_id) => {
+                    let copy = self.graph.duplicate_node(*node_id);
+                    let offset = Vec2::splat(DUPLICATE_OFFSET);
+                    self.node_positions
+                        .insert(copy, self.node_positions[*node_id] + offset);
+                    if let Some(size) = self.node_sizes.get(*node_id).copied() {
+                        self.node_sizes.insert(copy, size);
+                    }
+                    if self.collapsed_nodes.contains(node_id) {
+                        self.collapsed_nodes.insert(copy);
+                    }
+                    self.node_order.push(copy);
+                    self.selected_nodes.clear();
+                    self.selected_wires.clear();
+                    self.selected_nodes.insert(copy);
+                    extra_responses.push(NodeResponse::CreatedNode(copy));
+                }
+                NodeResponse::DisconnectAll(node_id) => {
+                    // Removed right away, see `delete_selection`
+                    for (input, output) in self.graph.disconnect_node(*node_id) {
+                        extra_responses.push(NodeResponse::DisconnectEvent { output, input });
+                    }
+                }
+                NodeResponse::ResizeNode { node, size } => {
+                    self.node_sizes.insert(*node, *size);
+                }
+                NodeResponse::ResetNodeSize(node_id) => {
+                    self.node_sizes.remove(*node_id);
+                }
+                NodeResponse::ToggleCollapsed(node_id) => {
+                    if !self.collapsed_nodes.remove(node_id) {
+                        self.collapsed_nodes.insert(*node_id);
+                    }
                 }
                 NodeResponse::RaiseNode(node_id) => {
                     let old_pos = self
@@ -581,16 +1542,22 @@ where
             }
         }
 
+        // The topmost node under the cursor
+        let node_under_cursor = self.node_order.iter().rev().copied().find(|node_id| {
+            node_rects
+                .get(node_id)
+                .is_some_and(|rect| rect.contains(cursor_pos))
+        });
+        let cursor_over_node = node_under_cursor.is_some();
+
         // Handle box selection
         if let Some(box_start) = self.ongoing_box_selection {
-            let selection_rect = Rect::from_two_pos(cursor_pos, box_start);
-            let bg_color = Color32::from_rgba_unmultiplied(200, 200, 200, 20);
-            let stroke_color = Color32::from_rgba_unmultiplied(200, 200, 200, 180);
+            let selection_rect = Rect::from_two_pos(cursor_pos, view.graph_to_screen(box_start));
             ui.painter().rect(
                 selection_rect,
                 2.0,
-                bg_color,
-                Stroke::new(3.0, stroke_color),
+                theme.box_selection_fill,
+                Stroke::new(3.0, theme.box_selection_stroke),
                 StrokeKind::Middle,
             );
             if !shift_pressed {
@@ -621,15 +1588,37 @@ where
         // This locks the context, so don't hold on to it for too long.
         let mouse = &ui.ctx().input(|i| i.pointer.clone());
 
-        if mouse.any_released() && self.connection_in_progress.is_some() {
-            self.connection_in_progress = None;
+        if mouse.any_released() {
+            if let Some((_, port)) = self.connection_in_progress.take() {
+                // Dropped over the empty canvas: offer to create a node to
+                // connect to.
+                let connected = delayed_responses
+                    .iter()
+                    .any(|response| matches!(response, NodeResponse::ConnectEventEnded { .. }));
+                if !connected && !cursor_over_node && cursor_in_editor && !cursor_in_finder {
+                    self.node_finder = Some(NodeFinder::new_connecting(screen_cursor_pos, port));
+                    self.wire_context_menu = None;
+                    self.node_context_menu = None;
+                }
+            }
         }
 
-        if mouse.secondary_released() && cursor_in_editor && !cursor_in_finder {
-            self.node_finder = Some(NodeFinder::new_at(cursor_pos));
+        if mouse.secondary_released() && cursor_in_editor && !cursor_in_finder && !knife_released {
+            self.node_finder = None;
+            self.wire_context_menu = None;
+            self.node_context_menu = None;
+            if let Some(node_id) = node_under_cursor {
+                self.node_context_menu = Some((node_id, screen_cursor_pos));
+            } else if let Some((input, _)) = hovered_wire {
+                self.wire_context_menu = Some((input, screen_cursor_pos));
+            } else {
+                self.node_finder = Some(NodeFinder::new_at(screen_cursor_pos));
+            }
         }
         if ui.ctx().input(|i| i.key_pressed(Key::Escape)) {
             self.node_finder = None;
+            self.wire_context_menu = None;
+            self.node_context_menu = None;
         }
 
         if r.dragged() && ui.ctx().input(|i| i.pointer.middle_down()) {
@@ -641,12 +1630,13 @@ where
         if click_on_background {
             if !shift_pressed {
                 self.selected_nodes.clear();
+                self.selected_wires.clear();
             }
             self.node_finder = None;
         }
 
         if drag_started_on_background && mouse.primary_down() {
-            self.ongoing_box_selection = Some(cursor_pos);
+            self.ongoing_box_selection = Some(view.screen_to_graph(cursor_pos));
         }
         if mouse.primary_released() || drag_released_on_background {
             self.ongoing_box_selection = None;
@@ -656,38 +1646,136 @@ where
             ui.output_mut(|output| output.cursor_icon = egui::CursorIcon::Grabbing);
         }
 
-        GraphResponse {
-            node_responses: delayed_responses,
-            cursor_in_editor,
-            cursor_in_finder,
-        }
+        (
+            GraphResponse {
+                node_responses: delayed_responses,
+                cursor_in_editor,
+                cursor_in_finder,
+            },
+            view_command,
+        )
     }
 }
 
-fn draw_connection(
+/// The id of the interaction covering a whole node, used to drag it around.
+fn node_window_id(node_id: NodeId) -> Id {
+    Id::new((node_id, "window"))
+}
+
+/// The pointer position in the coordinate system of the `ui`'s layer. This is
+/// the screen position unless the layer is transformed.
+fn local_pointer_pos(ui: &Ui) -> Option<Pos2> {
+    let pos = ui.ctx().pointer_hover_pos()?;
+    Some(match ui.ctx().layer_transform_from_global(ui.layer_id()) {
+        Some(from_global) => from_global * pos,
+        None => pos,
+    })
+}
+
+/// The polyline of the wire between two ports, in the space of `pan_zoom`.
+/// Routed wires are cached by their `input` end, or as the connection being
+/// dragged when there is none.
+#[allow(clippy::too_many_arguments)]
+fn wire_points(
+    wire_style: WireStyle,
+    wire_routes: &mut WireRouteCache,
+    node_rects: &NodeRects,
     pan_zoom: &PanZoom,
-    painter: &Painter,
+    input: Option<InputId>,
     src_pos: Pos2,
     dst_pos: Pos2,
-    color: Color32,
+    orientations: [NodeOrientation; 2],
+) -> Vec<Pos2> {
+    match wire_style {
+        WireStyle::Routed => {
+            let src = pan_zoom.screen_to_graph(src_pos);
+            let dst = pan_zoom.screen_to_graph(dst_pos);
+            let route = wire_routes.route(input, src, dst, orientations, node_rects);
+            route
+                .into_iter()
+                .map(|pos| pan_zoom.graph_to_screen(pos))
+                .collect()
+        }
+        _ => wire_style.oriented_points(src_pos, dst_pos, orientations, pan_zoom.zoom),
+    }
+}
+
+/// Draws a connection along `points`, over a wider `halo` line when the
+/// connection is hovered or selected.
+fn draw_connection<DataType: DataTypeTrait<UserState>, UserState>(
+    pan_zoom: &PanZoom,
+    theme: &GraphTheme,
+    painter: &Painter,
+    points: Vec<Pos2>,
+    port_type: &DataType,
+    user_state: &mut UserState,
+    halo: Option<Color32>,
 ) {
+    let width = port_type.wire_width(user_state).unwrap_or(theme.wire_width);
     let connection_stroke = egui::Stroke {
-        width: 5.0 * pan_zoom.zoom,
-        color,
+        width: width * pan_zoom.zoom,
+        color: port_type.data_type_color(user_state),
     };
+    let dash_pattern = port_type
+        .wire_dash_pattern(user_state)
+        .map(|(dash, gap)| (dash * pan_zoom.zoom, gap * pan_zoom.zoom));
 
-    let control_scale = ((dst_pos.x - src_pos.x) * 0.333).max(30.0) * pan_zoom.zoom;
-    let src_control = src_pos + Vec2::X * control_scale;
-    let dst_control = dst_pos - Vec2::X * control_scale;
+    if let Some(halo) = halo {
+        let halo_stroke = Stroke::new((width + 4.0) * pan_zoom.zoom, halo);
+        draw_wire(painter, points.clone(), halo_stroke, None);
+    }
+    draw_wire(painter, points, connection_stroke, dash_pattern);
+}
+
+/// The entries of the node context menu, listed before the ones added by
+/// [`NodeDataTrait::context_menu_ui`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NodeMenuAction {
+    Duplicate,
+    Delete,
+    DisconnectAll,
+    ToggleCollapsed,
+    ResetSize,
+    BringToFront,
+}
 
-    let bezier = CubicBezierShape::from_points_stroke(
-        [src_pos, src_control, dst_control, dst_pos],
-        false,
-        Color32::TRANSPARENT,
-        connection_stroke,
-    );
+impl NodeMenuAction {
+    /// All the actions, in the order the menu lists them.
+    const ALL: [NodeMenuAction; 6] = [
+        NodeMenuAction::Duplicate,
+        NodeMenuAction::Delete,
+        NodeMenuAction::DisconnectAll,
+        NodeMenuAction::ToggleCollapsed,
+        NodeMenuAction::ResetSize,
+        NodeMenuAction::BringToFront,
+    ];
+
+    fn label(self, collapsed: bool) -> &'static str {
+        match self {
+            NodeMenuAction::Duplicate => "Duplicate",
+            NodeMenuAction::Delete => "Delete",
+            NodeMenuAction::DisconnectAll => "Disconnect all",
+            NodeMenuAction::ToggleCollapsed if collapsed => "Expand",
+            NodeMenuAction::ToggleCollapsed => "Collapse",
+            NodeMenuAction::ResetSize => "Reset size",
+            NodeMenuAction::BringToFront => "Bring to front",
+        }
+    }
 
-    painter.add(bezier);
+    /// What choosing the action in the context menu of the node does.
+    fn response<UserResponse: UserResponseTrait, NodeData: NodeDataTrait>(
+        self,
+        node_id: NodeId,
+    ) -> NodeResponse<UserResponse, NodeData> {
+        match self {
+            NodeMenuAction::Duplicate => NodeResponse::DuplicateNode(node_id),
+            NodeMenuAction::Delete => NodeResponse::DeleteNodeUi(node_id),
+            NodeMenuAction::DisconnectAll => NodeResponse::DisconnectAll(node_id),
+            NodeMenuAction::ToggleCollapsed => NodeResponse::ToggleCollapsed(node_id),
+            NodeMenuAction::ResetSize => NodeResponse::ResetNodeSize(node_id),
+            NodeMenuAction::BringToFront => NodeResponse::RaiseNode(node_id),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -708,6 +1796,7 @@ where
     DataType: DataTypeTrait<UserState>,
     UserState: UserStateTrait,
 {
+    /// The largest size of the nodes that weren't resized, in graph space.
     pub const MAX_NODE_SIZE: [f32; 2] = [200.0, 200.0];
 
     pub fn show(
@@ -717,7 +1806,10 @@ where
         user_state: &mut UserState,
     ) -> Vec<NodeResponse<UserResponse, NodeData>> {
         let mut child_ui = ui.new_child(egui::UiBuilder::new().id_salt(self.node_id).max_rect(
-            Rect::from_min_size(*self.position + self.pan, Self::MAX_NODE_SIZE.into()),
+            Rect::from_min_size(
+                pan_zoom.graph_to_screen(*self.position),
+                self.size.unwrap_or(Self::MAX_NODE_SIZE.into()),
+            ),
         ));
 
         Self::show_graph_node(self, pan_zoom, &mut child_ui, user_state)
@@ -734,18 +1826,9 @@ where
         let margin = egui::vec2(15.0, 5.0) * pan_zoom.zoom;
         let mut responses = Vec::<NodeResponse<UserResponse, NodeData>>::new();
 
-        let background_color;
-        let text_color;
-        if ui.visuals().dark_mode {
-            background_color = color_from_hex("#3f3f3f").unwrap();
-            text_color = color_from_hex("#fefefe").unwrap();
-        } else {
-            background_color = color_from_hex("#ffffff").unwrap();
-            text_color = color_from_hex("#505050").unwrap();
-        }
-
+        let background_color = self.theme.node_background;
         ui.visuals_mut().widgets.noninteractive.fg_stroke =
-            Stroke::new(2.0 * pan_zoom.zoom, text_color);
+            Stroke::new(2.0 * pan_zoom.zoom, self.theme.node_text);
 
         // Preallocate shapes to paint below contents
         let outline_shape = ui.painter().add(Shape::Noop);
@@ -769,6 +1852,10 @@ where
                 .max_rect(inner_rect)
                 .layout(*ui.layout()),
         );
+        // A resized node takes all of its size, not just what its contents need
+        if self.size.is_some() && !self.collapsed {
+            child_ui.set_min_size(inner_rect.size());
+        }
 
         // Get interaction rect from memory, it may expand after the window response on resize.
         let interaction_rect = ui
@@ -783,7 +1870,7 @@ where
         // before creating the node content.
         let window_response = ui.interact(
             interaction_rect,
-            Id::new((self.node_id, "window")),
+            node_window_id(self.node_id),
             Sense::click_and_drag(),
         );
 
@@ -801,9 +1888,14 @@ where
                     user_state,
                     &self.graph[self.node_id].label,
                 ));
+                // Room for the titlebar buttons
+                ui.add_space(2.0 * TITLEBAR_BUTTON_SPACING * pan_zoom.zoom);
             });
             ui.add_space(margin.y);
             title_height = ui.min_size().y;
+            if self.collapsed {
+                return;
+            }
 
             // First pass: Draw the inner fields. Compute port heights
             let inputs = self.graph[self.node_id].inputs.clone();
@@ -857,12 +1949,13 @@ where
             let outputs = self.graph[self.node_id].outputs.clone();
             for (param_name, param_id) in outputs {
                 let height_before = ui.min_rect().bottom();
-                responses.extend(
-                    self.graph[self.node_id]
-                        .user_data
-                        .output_ui(ui, self.node_id, self.graph, user_state, &param_name)
-                        .into_iter(),
-                );
+                responses.extend(self.graph[self.node_id].user_data.output_ui(
+                    ui,
+                    self.node_id,
+                    self.graph,
+                    user_state,
+                    &param_name,
+                ));
 
                 self.graph[self.node_id].user_data.separator(
                     ui,
@@ -890,6 +1983,16 @@ where
         let outer_rect = child_ui.min_rect().expand2(margin);
         let port_left = outer_rect.left();
         let port_right = outer_rect.right();
+        let titlebar_rect = Rect::from_min_size(
+            outer_rect.min,
+            vec2(outer_rect.width(), title_height + margin.y),
+        );
+        if self.collapsed {
+            // All the ports on the edges of the titlebar
+            let node = &self.graph[self.node_id];
+            input_port_heights = vec![titlebar_rect.center().y; node.inputs.len()];
+            output_port_heights = vec![titlebar_rect.center().y; node.outputs.len()];
+        }
 
         // Save expanded rect to memory.
         ui.ctx().memory_mut(|mem| {
@@ -910,6 +2013,8 @@ where
             port_locations: &mut PortLocations,
             ongoing_drag: Option<(NodeId, AnyParameterId)>,
             is_connected_input: bool,
+            interactive: bool,
+            theme: &GraphTheme,
         ) where
             DataType: DataTypeTrait<UserState>,
             UserResponse: UserResponseTrait,
@@ -924,7 +2029,7 @@ where
                 egui::Vec2::splat(2. * DISTANCE_TO_CONNECT * pan_zoom.zoom),
             );
 
-            let sense = if ongoing_drag.is_some() {
+            let sense = if ongoing_drag.is_some() || !interactive {
                 Sense::hover()
             } else {
                 Sense::click_and_drag()
@@ -933,7 +2038,9 @@ where
             let resp = ui.allocate_rect(port_rect, sense);
 
             // Check if the distance between the port and the mouse is the distance to connect
-            let close_enough = if let Some(pointer_pos) = ui.ctx().pointer_hover_pos() {
+            let close_enough = if !interactive {
+                false
+            } else if let Some(pointer_pos) = local_pointer_pos(ui) {
                 port_rect.center().distance(pointer_pos) < DISTANCE_TO_CONNECT * pan_zoom.zoom
             } else {
                 false
@@ -948,7 +2055,7 @@ where
                         egui::CursorIcon::Grab
                     }
                 });
-                Color32::WHITE
+                theme.port_hover
             } else {
                 port_type.data_type_color(user_state)
             };
@@ -995,76 +2102,22 @@ where
             port_locations.insert(param_id, port_rect.center());
         }
 
-        // Input ports
-        for ((_, param), port_height) in self.graph[self.node_id]
-            .inputs
-            .iter()
-            .zip(input_port_heights.into_iter())
-        {
-            let should_draw = match self.graph[*param].kind() {
-                InputParamKind::ConnectionOnly => true,
-                InputParamKind::ConstantOnly => false,
-                InputParamKind::ConnectionOrConstant => true,
-            };
-
-            if should_draw {
-                let pos_left = pos2(port_left, port_height);
-                draw_port(
-                    pan_zoom,
-                    ui,
-                    self.graph,
-                    self.node_id,
-                    user_state,
-                    pos_left,
-                    &mut responses,
-                    AnyParameterId::Input(*param),
-                    self.port_locations,
-                    self.ongoing_drag,
-                    self.graph.connection(*param).is_some(),
-                );
-            }
-        }
-
-        // Output ports
-        for ((_, param), port_height) in self.graph[self.node_id]
-            .outputs
-            .iter()
-            .zip(output_port_heights.into_iter())
-        {
-            let pos_right = pos2(port_right, port_height);
-            draw_port(
-                pan_zoom,
-                ui,
-                self.graph,
-                self.node_id,
-                user_state,
-                pos_right,
-                &mut responses,
-                AnyParameterId::Output(*param),
-                self.port_locations,
-                self.ongoing_drag,
-                false,
-            );
-        }
-
         // Draw the background shape.
         // NOTE: This code is a bit more involved than it needs to be because egui
         // does not support drawing rectangles with asymmetrical round corners.
 
-        let (shape, outline) = {
+        let (shape, outline, node_rect) = {
             let corner_radius = 4.0 * pan_zoom.zoom;
             let corner = CornerRadiusF32::same(corner_radius);
 
-            let titlebar_height = title_height + margin.y;
-            let titlebar_rect =
-                Rect::from_min_size(outer_rect.min, vec2(outer_rect.width(), titlebar_height));
+            let titlebar_height = titlebar_rect.height();
             let titlebar = Shape::Rect(RectShape::new(
                 titlebar_rect,
                 corner,
                 self.graph[self.node_id]
                     .user_data
                     .titlebar_color(ui, self.node_id, self.graph, user_state)
-                    .unwrap_or_else(|| background_color.lighten(0.8)),
+                    .unwrap_or(self.theme.node_titlebar),
                 Stroke::NONE,
                 StrokeKind::Inside,
             ));
@@ -1098,7 +2151,7 @@ where
                 Shape::Rect(RectShape::new(
                     node_rect.expand(3.0 * pan_zoom.zoom),
                     corner,
-                    Color32::WHITE.lighten(0.8),
+                    self.theme.selection_outline,
                     Stroke::NONE,
                     StrokeKind::Inside,
                 ))
@@ -1109,12 +2162,117 @@ where
             // Take note of the node rect, so the editor can use it later to compute intersections.
             self.node_rects.insert(self.node_id, node_rect);
 
-            (Shape::Vec(vec![titlebar, body, bottom_body]), outline)
+            (
+                Shape::Vec(vec![titlebar, body, bottom_body]),
+                outline,
+                node_rect,
+            )
         };
 
         ui.painter().set(background_shape, shape);
         ui.painter().set(outline_shape, outline);
 
+        // Vertical nodes have their ports spread along the top and bottom of
+        // the drawn node, which goes a bit past `outer_rect`
+        let (input_port_positions, output_port_positions): (Vec<Pos2>, Vec<Pos2>) =
+            match self.orientation {
+                NodeOrientation::Horizontal => (
+                    input_port_heights
+                        .into_iter()
+                        .map(|height| pos2(port_left, height))
+                        .collect(),
+                    output_port_heights
+                        .into_iter()
+                        .map(|height| pos2(port_right, height))
+                        .collect(),
+                ),
+                NodeOrientation::Vertical => {
+                    let spread = |index: usize, count: usize, y: f32| {
+                        let t = if self.collapsed {
+                            0.5
+                        } else {
+                            (index + 1) as f32 / (count + 1) as f32
+                        };
+                        pos2(lerp(node_rect.left()..=node_rect.right(), t), y)
+                    };
+                    let node = &self.graph[self.node_id];
+                    let connectable = |input: &InputId| {
+                        !matches!(self.graph[*input].kind(), InputParamKind::ConstantOnly)
+                    };
+                    let input_count = node.input_ids().filter(connectable).count();
+                    let mut input_index = 0;
+                    let inputs = node
+                        .input_ids()
+                        .map(|input| {
+                            if !connectable(&input) {
+                                // Not drawn
+                                return node_rect.center_top();
+                            }
+                            input_index += 1;
+                            spread(input_index - 1, input_count, node_rect.top())
+                        })
+                        .collect();
+                    let outputs = (0..node.outputs.len())
+                        .map(|index| spread(index, node.outputs.len(), node_rect.bottom()))
+                        .collect();
+                    (inputs, outputs)
+                }
+            };
+
+        // Input ports
+        for ((_, param), port_pos) in self.graph[self.node_id]
+            .inputs
+            .iter()
+            .zip(input_port_positions)
+        {
+            let should_draw = match self.graph[*param].kind() {
+                InputParamKind::ConnectionOnly => true,
+                InputParamKind::ConstantOnly => false,
+                InputParamKind::ConnectionOrConstant => true,
+            };
+
+            if should_draw {
+                draw_port(
+                    pan_zoom,
+                    ui,
+                    self.graph,
+                    self.node_id,
+                    user_state,
+                    port_pos,
+                    &mut responses,
+                    AnyParameterId::Input(*param),
+                    self.port_locations,
+                    self.ongoing_drag,
+                    self.graph.connection(*param).is_some(),
+                    !self.collapsed,
+                    self.theme,
+                );
+            }
+        }
+
+        // Output ports
+        for ((_, param), port_pos) in self.graph[self.node_id]
+            .outputs
+            .iter()
+            .zip(output_port_positions)
+        {
+            draw_port(
+                pan_zoom,
+                ui,
+                self.graph,
+                self.node_id,
+                user_state,
+                port_pos,
+                &mut responses,
+                AnyParameterId::Output(*param),
+                self.port_locations,
+                self.ongoing_drag,
+                false,
+                !self.collapsed,
+                self.theme,
+            );
+        }
+
         // --- Interaction ---
 
         // Titlebar buttons
@@ -1124,12 +2282,40 @@ where
             user_state,
         );
 
-        if can_delete && Self::close_button(pan_zoom, ui, outer_rect).clicked() {
+        if can_delete && Self::close_button(pan_zoom, self.theme, ui, outer_rect).clicked() {
             responses.push(NodeResponse::DeleteNodeUi(self.node_id));
         }
+        if !self.collapsed {
+            let (min_size, max_size) = self.graph[self.node_id].user_data.size_limits(
+                self.node_id,
+                self.graph,
+                user_state,
+            );
+            // Limits the wrong way around would make `clamp` panic
+            let max_size = max_size.max(min_size);
+            let resize = Self::resize_handle(pan_zoom, self.theme, ui, self.node_id, outer_rect);
+            if resize.dragged() {
+                // From the stored size, which the contents may overflow
+                let size = self.size.unwrap_or(outer_rect.size() / pan_zoom.zoom)
+                    + resize.drag_delta() / pan_zoom.zoom;
+                responses.push(NodeResponse::ResizeNode {
+                    node: self.node_id,
+                    size: size.clamp(min_size, max_size),
+                });
+            }
+        }
+        let collapse_clicked =
+            Self::collapse_button(pan_zoom, self.theme, ui, outer_rect, self.collapsed).clicked();
+        let title_double_clicked = window_response.double_clicked()
+            && window_response
+                .interact_pointer_pos()
+                .is_some_and(|pos| titlebar_rect.contains(pos));
+        if collapse_clicked || title_double_clicked {
+            responses.push(NodeResponse::ToggleCollapsed(self.node_id));
+        }
 
-        // Movement
-        let drag_delta = window_response.drag_delta();
+        // Movement, converted to graph space
+        let drag_delta = window_response.drag_delta() / pan_zoom.zoom;
         if drag_delta.length_sq() > 0.0 {
             responses.push(NodeResponse::MoveNode {
                 node: self.node_id,
@@ -1153,40 +2339,47 @@ where
         responses
     }
 
-    fn close_button(pan_zoom: &PanZoom, ui: &mut Ui, node_rect: Rect) -> Response {
+    /// Allocates the button in the given `slot` of the titlebar, counting from
+    /// the right, and returns it with the color to paint it with.
+    fn titlebar_button(
+        pan_zoom: &PanZoom,
+        theme: &GraphTheme,
+        ui: &mut Ui,
+        node_rect: Rect,
+        slot: usize,
+    ) -> (Response, Color32) {
         // Measurements
         let margin = 8.0 * pan_zoom.zoom;
         let size = 10.0 * pan_zoom.zoom;
-        let stroke_width = 2.0 * pan_zoom.zoom;
         let offs = margin + size * 0.5;
 
-        let position = pos2(node_rect.right() - offs, node_rect.top() + offs);
+        let position = pos2(
+            node_rect.right() - offs - slot as f32 * TITLEBAR_BUTTON_SPACING * pan_zoom.zoom,
+            node_rect.top() + offs,
+        );
         let rect = Rect::from_center_size(position, vec2(size, size));
         let resp = ui.allocate_rect(rect, Sense::click());
 
-        let dark_mode = ui.visuals().dark_mode;
         let color = if resp.clicked() {
-            if dark_mode {
-                color_from_hex("#ffffff").unwrap()
-            } else {
-                color_from_hex("#000000").unwrap()
-            }
+            theme.close_button_pressed
         } else if resp.hovered() {
-            if dark_mode {
-                color_from_hex("#dddddd").unwrap()
-            } else {
-                color_from_hex("#222222").unwrap()
-            }
+            theme.close_button_hovered
         } else {
-            #[allow(clippy::collapsible_else_if)]
-            if dark_mode {
-                color_from_hex("#aaaaaa").unwrap()
-            } else {
-                color_from_hex("#555555").unwrap()
-            }
+            theme.close_button
         };
+        (resp, color)
+    }
+
+    fn close_button(
+        pan_zoom: &PanZoom,
+        theme: &GraphTheme,
+        ui: &mut Ui,
+        node_rect: Rect,
+    ) -> Response {
+        let (resp, color) = Self::titlebar_button(pan_zoom, theme, ui, node_rect, 0);
+        let rect = resp.rect;
         let stroke = Stroke {
-            width: stroke_width,
+            width: 2.0 * pan_zoom.zoom,
             color,
         };
 
@@ -1197,4 +2390,372 @@ where
 
         resp
     }
+
+    /// The handle at the bottom right corner of the node, dragged to resize
+    /// it.
+    fn resize_handle(
+        pan_zoom: &PanZoom,
+        theme: &GraphTheme,
+        ui: &mut Ui,
+        node_id: NodeId,
+        node_rect: Rect,
+    ) -> Response {
+        let size = 10.0 * pan_zoom.zoom;
+        let rect = Rect::from_min_max(
+            node_rect.right_bottom() - vec2(size, size),
+            node_rect.right_bottom(),
+        );
+        let resp = ui.interact(rect, node_window_id(node_id).with("resize"), Sense::drag());
+
+        let color = if resp.dragged() {
+            theme.resize_handle_pressed
+        } else if resp.hovered() {
+            theme.resize_handle_hovered
+        } else {
+            theme.resize_handle
+        };
+        if resp.hovered() || resp.dragged() {
+            ui.output_mut(|output| output.cursor_icon = egui::CursorIcon::ResizeNwSe);
+        }
+        let stroke = Stroke::new(1.5 * pan_zoom.zoom, color);
+        for fraction in [0.3, 0.7] {
+            let offset = size * fraction;
+            ui.painter().line_segment(
+                [
+                    rect.right_bottom() - vec2(offset, 0.0),
+                    rect.right_bottom() - vec2(0.0, offset),
+                ],
+                stroke,
+            );
+        }
+
+        resp
+    }
+
+    /// The button next to the close button, with an arrow pointing down when
+    /// the node is expanded and right when it is collapsed.
+    fn collapse_button(
+        pan_zoom: &PanZoom,
+        theme: &GraphTheme,
+        ui: &mut Ui,
+        node_rect: Rect,
+        collapsed: bool,
+    ) -> Response {
+        let (resp, color) = Self::titlebar_button(pan_zoom, theme, ui, node_rect, 1);
+        let rect = resp.rect;
+        let points = if collapsed {
+            vec![rect.left_top(), rect.right_center(), rect.left_bottom()]
+        } else {
+            vec![rect.left_top(), rect.right_top(), rect.center_bottom()]
+        };
+        ui.painter()
+            .add(Shape::convex_polygon(points, color, Stroke::NONE));
+
+        resp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    pub fn test_run_command() {
+        let mut editor = TestEditor::default();
+        let template = TestTemplate::new("Scalar add", "Math");
+        let nodes: HashSet<NodeId> = [pos2(0.0, 0.0), pos2(200.0, 0.0)]
+            .into_iter()
+            .map(|pos| add_test_node(&mut editor, &template, pos))
+            .collect();
+        let commands = vec![TestCommand("Clear selection")];
+        let mut extra_responses = Vec::new();
+        let mut run = |editor: &mut TestEditor, id: EditorCommandId| {
+            editor.run_command(&id, &commands, &mut TestState, &mut extra_responses)
+        };
+
+        let (responses, view_command) = run(
+            &mut editor,
+            EditorCommandId::Builtin(BuiltinCommand::SelectAll),
+        );
+        assert!(responses.is_empty() && view_command.is_none());
+        assert_eq!(editor.selected_nodes, nodes);
+
+        // View commands are left to the caller
+        let (_, view_command) = run(
+            &mut editor,
+            EditorCommandId::Builtin(BuiltinCommand::ZoomToFit),
+        );
+        assert_eq!(view_command, Some(BuiltinCommand::ZoomToFit));
+
+        // User commands are found by id
+        let (responses, _) = run(
+            &mut editor,
+            EditorCommandId::User("Clear selection".to_owned()),
+        );
+        assert!(matches!(
+            responses.as_slice(),
+            [NodeResponse::User(TestResponse)]
+        ));
+        assert!(editor.selected_nodes.is_empty());
+
+        // A command that is no longer listed doesn't run
+        editor.select_all();
+        let (responses, _) = run(&mut editor, EditorCommandId::User("Removed".to_owned()));
+        assert!(responses.is_empty());
+        assert_eq!(editor.selected_nodes, nodes);
+    }
+
+    #[test]
+    pub fn test_node_menu_actions() {
+        let mut editor = TestEditor::default();
+        let template = TestTemplate::new("Scalar add", "Math");
+        let node = add_test_node(&mut editor, &template, Pos2::ZERO);
+        let response = |action: NodeMenuAction| -> NodeResponse<TestResponse, TestNodeData> {
+            action.response(node)
+        };
+
+        assert!(matches!(
+            response(NodeMenuAction::Duplicate),
+            NodeResponse::DuplicateNode(id) if id == node
+        ));
+        assert!(editor.node_menu_enabled(NodeMenuAction::Delete, node, &mut TestState));
+        assert!(matches!(
+            response(NodeMenuAction::Delete),
+            NodeResponse::DeleteNodeUi(id) if id == node
+        ));
+        assert!(matches!(
+            response(NodeMenuAction::DisconnectAll),
+            NodeResponse::DisconnectAll(id) if id == node
+        ));
+        assert!(matches!(
+            response(NodeMenuAction::ToggleCollapsed),
+            NodeResponse::ToggleCollapsed(id) if id == node
+        ));
+        assert!(matches!(
+            response(NodeMenuAction::BringToFront),
+            NodeResponse::RaiseNode(id) if id == node
+        ));
+
+        // Only a resized node can have its size reset
+        assert!(!editor.node_menu_enabled(NodeMenuAction::ResetSize, node, &mut TestState));
+        editor.node_sizes.insert(node, vec2(300.0, 200.0));
+        assert!(editor.node_menu_enabled(NodeMenuAction::ResetSize, node, &mut TestState));
+        assert!(matches!(
+            response(NodeMenuAction::ResetSize),
+            NodeResponse::ResetNodeSize(id) if id == node
+        ));
+    }
+
+    /// Draws the node on its own for a frame, returning where its ports are
+    /// and its rect.
+    fn show_node(
+        ctx: &Context,
+        editor: &mut TestEditor,
+        node: NodeId,
+        collapsed: bool,
+        orientation: NodeOrientation,
+    ) -> (PortLocations, Rect) {
+        let mut port_locations = PortLocations::new();
+        let mut node_rects = NodeRects::new();
+        run_frame(ctx, Vec::new(), |ui| {
+            GraphNodeWidget {
+                position: editor.node_positions.get_mut(node).unwrap(),
+                graph: &mut editor.graph,
+                port_locations: &mut port_locations,
+                node_rects: &mut node_rects,
+                node_id: node,
+                ongoing_drag: None,
+                selected: false,
+                size: None,
+                collapsed,
+                orientation,
+                theme: &GraphTheme::DARK,
+            }
+            .show(&PanZoom::default(), ui, &mut TestState);
+        });
+        (port_locations, node_rects[&node])
+    }
+
+    #[test]
+    pub fn test_collapsed_ports() {
+        let mut editor = TestEditor::default();
+        let template = TestTemplate::new("Scalar add", "Math");
+        let node = add_test_node(&mut editor, &template, pos2(100.0, 100.0));
+        let input = AnyParameterId::Input(editor.graph[node].get_input("a").unwrap());
+        let output = AnyParameterId::Output(editor.graph[node].get_output("out").unwrap());
+
+        let ctx = Context::default();
+        let horizontal = NodeOrientation::Horizontal;
+        let (expanded_ports, expanded_rect) = show_node(&ctx, &mut editor, node, false, horizontal);
+        let (ports, rect) = show_node(&ctx, &mut editor, node, true, horizontal);
+
+        // The ports stay on the edges, across from each other in the titlebar
+        assert_eq!(ports[&input].x, rect.left());
+        assert_eq!(ports[&output].x, rect.right());
+        assert_eq!(ports[&input].y, ports[&output].y);
+        assert!(rect.height() < expanded_rect.height());
+        assert!(ports[&input].y < rect.bottom());
+        assert!(ports[&input].y < expanded_ports[&input].y);
+    }
+
+    #[test]
+    pub fn test_vertical_ports() {
+        let mut editor = TestEditor::default();
+        let template = TestTemplate::new("Scalar add", "Math");
+        let node = add_test_node(&mut editor, &template, pos2(100.0, 100.0));
+        let mut add_input = |name: &str, kind| {
+            let input = editor.graph.add_input_param(
+                node,
+                name.to_owned(),
+                TestType::Scalar,
+                TestValue(0.0),
+                kind,
+                true,
+            );
+            AnyParameterId::Input(input)
+        };
+        let constant = add_input("constant", InputParamKind::ConstantOnly);
+        let second = add_input("b", InputParamKind::ConnectionOnly);
+        let first = AnyParameterId::Input(editor.graph[node].get_input("a").unwrap());
+        let output = AnyParameterId::Output(editor.graph[node].get_output("out").unwrap());
+
+        let ctx = Context::default();
+        let vertical = NodeOrientation::Vertical;
+        let (ports, rect) = show_node(&ctx, &mut editor, node, false, vertical);
+
+        // Inputs spread along the top, skipping the constant, outputs along
+        // the bottom
+        let x = |t: f32| lerp(rect.left()..=rect.right(), t);
+        assert_eq!(ports[&first], pos2(x(1.0 / 3.0), rect.top()));
+        assert_eq!(ports[&second], pos2(x(2.0 / 3.0), rect.top()));
+        assert!(!ports.contains_key(&constant));
+        assert_eq!(ports[&output], pos2(x(0.5), rect.bottom()));
+
+        // Collapsed, they are all centered
+        let (ports, rect) = show_node(&ctx, &mut editor, node, true, vertical);
+        assert_eq!(ports[&first], rect.center_top());
+        assert_eq!(ports[&second], rect.center_top());
+        assert_eq!(ports[&output], rect.center_bottom());
+    }
+
+    #[test]
+    pub fn test_resize_node() {
+        let mut editor = TestEditor::default();
+        let template = TestTemplate::new("Scalar add", "Math");
+        let node = add_test_node(&mut editor, &template, pos2(100.0, 100.0));
+        editor.node_sizes.insert(node, vec2(150.0, 150.0));
+        let pan_zoom = PanZoom {
+            pan: vec2(50.0, 20.0),
+            zoom: 2.0,
+            ..Default::default()
+        };
+
+        // Draws the node and applies its responses, like the editor does
+        let ctx = Context::default();
+        let mut moved = false;
+        let mut frame = |editor: &mut TestEditor, events: Vec<Event>| {
+            let mut port_locations = PortLocations::new();
+            let mut node_rects = NodeRects::new();
+            run_frame(&ctx, events, |ui| {
+                let responses = GraphNodeWidget {
+                    position: editor.node_positions.get_mut(node).unwrap(),
+                    graph: &mut editor.graph,
+                    port_locations: &mut port_locations,
+                    node_rects: &mut node_rects,
+                    node_id: node,
+                    ongoing_drag: None,
+                    selected: false,
+                    size: editor.node_sizes.get(node).copied(),
+                    collapsed: false,
+                    orientation: NodeOrientation::Horizontal,
+                    theme: &GraphTheme::DARK,
+                }
+                .show(&pan_zoom, ui, &mut TestState);
+                for response in responses {
+                    match response {
+                        NodeResponse::ResizeNode { node, size } => {
+                            editor.node_sizes.insert(node, size);
+                        }
+                        NodeResponse::MoveNode { .. } => moved = true,
+                        _ => {}
+                    }
+                }
+            });
+            node_rects[&node]
+        };
+
+        // The first frame stores the node rect in `OuterRectMemory`, where the
+        // node drag is sensed from on the next frames, under the handle
+        let rect = frame(&mut editor, Vec::new());
+        assert_eq!(rect.width(), 300.0);
+        // The drawn rect goes a bit past the bottom of the node
+        let handle = rect.min + vec2(300.0, 300.0) - vec2(4.0, 4.0);
+        frame(
+            &mut editor,
+            vec![Event::PointerMoved(handle), primary_button(handle, true)],
+        );
+
+        // Dragging by 40 points on screen grows it by 20 in graph space. It
+        // starts from the stored size, even when the contents overflow it.
+        editor.node_sizes.insert(node, vec2(150.0, 50.0));
+        for step in 1..=4 {
+            frame(
+                &mut editor,
+                vec![Event::PointerMoved(handle + vec2(10.0 * step as f32, 0.0))],
+            );
+        }
+        assert_eq!(editor.node_sizes[node], vec2(170.0, 50.0));
+
+        // It stops growing at the largest size
+        let end = handle + vec2(200.0, 0.0);
+        frame(&mut editor, vec![Event::PointerMoved(end)]);
+        frame(&mut editor, vec![primary_button(end, false)]);
+        assert_eq!(editor.node_sizes[node], vec2(TEST_SIZE_LIMITS.1.x, 50.0));
+        assert!(!moved, "The resize handle should win over the node drag");
+    }
+
+    #[test]
+    pub fn test_drop_palette_template() {
+        let mut editor = TestEditor::default();
+        editor.pan_zoom.pan = vec2(40.0, 20.0);
+        editor.pan_zoom.zoom = 2.0;
+        let templates = vec![
+            TestTemplate::new("Scalar add", "Math"),
+            TestTemplate::new("Scalar sub", "Math"),
+        ];
+
+        let ctx = Context::default();
+        let mut responses = Vec::new();
+        let mut frame = |editor: &mut TestEditor, events: Vec<Event>| {
+            run_frame(&ctx, events, |ui| {
+                let response =
+                    editor.draw_graph_editor(ui, templates.clone(), &mut TestState, Vec::new());
+                responses.extend(response.node_responses);
+            });
+        };
+        let drop_pos = pos2(400.0, 300.0);
+        frame(&mut editor, vec![Event::PointerMoved(drop_pos)]);
+
+        // Released over the editor, as if dragged from the palette
+        DragAndDrop::set_payload(&ctx, NodePalettePayload("Scalar sub".to_owned()));
+        frame(&mut editor, vec![primary_button(drop_pos, false)]);
+
+        let created: Vec<NodeId> = responses
+            .iter()
+            .filter_map(|response| match response {
+                NodeResponse::CreatedNode(node) => Some(*node),
+                _ => None,
+            })
+            .collect();
+        let [node] = created.as_slice() else {
+            panic!("Expected one created node, got {created:?}");
+        };
+        assert_eq!(editor.graph[*node].label, "Scalar sub");
+        assert_eq!(
+            editor.node_positions[*node],
+            editor.pan_zoom.screen_to_graph(drop_pos)
+        );
+        assert_ne!(editor.node_positions[*node], drop_pos);
+    }
 }
@@ -82,12 +82,12 @@ impl<
         shown_inline: bool,
     ) -> Self {
         Self {
-            id: id,
-            typ: typ,
-            value: value,
-            kind: kind,
-            node: node,
-            shown_inline: shown_inline,
+            id,
+            typ,
+            value,
+            kind,
+            node,
+            shown_inline,
             _phantom: Default::default(),
         }
     }
@@ -124,9 +124,9 @@ impl<DataType: DataTypeTrait<UserState>, UserState: UserStateTrait>
 {
     pub fn new(id: OutputId, typ: DataType, node: NodeId) -> Self {
         Self {
-            id: id,
-            typ: typ,
-            node: node,
+            id,
+            typ,
+            node,
             _phantom: Default::default(),
         }
     }
@@ -153,3 +153,43 @@ pub struct Graph<
     // produces it
     pub connections: SecondaryMap<InputId, OutputId>,
 }
+
+/// The ports of a node, without the graph around it. See
+/// [`NodeTemplateTrait::node_signature`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct NodeSignature<DataType> {
+    pub inputs: Vec<InputSignature<DataType>>,
+    pub outputs: Vec<OutputSignature<DataType>>,
+}
+
+/// An input port in a [`NodeSignature`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct InputSignature<DataType> {
+    pub name: String,
+    pub typ: DataType,
+    pub kind: InputParamKind,
+}
+
+impl<DataType: PartialEq> NodeSignature<DataType> {
+    /// Whether some input can be connected to an output of type `typ`.
+    pub fn accepts(&self, typ: &DataType) -> bool {
+        self.inputs
+            .iter()
+            .any(|input| input.typ == *typ && !matches!(input.kind, InputParamKind::ConstantOnly))
+    }
+
+    /// Whether some output can be connected to an input of type `typ`.
+    pub fn produces(&self, typ: &DataType) -> bool {
+        self.outputs.iter().any(|output| output.typ == *typ)
+    }
+}
+
+/// An output port in a [`NodeSignature`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct OutputSignature<DataType> {
+    pub name: String,
+    pub typ: DataType,
+}
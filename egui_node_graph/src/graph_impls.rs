@@ -260,16 +260,7 @@ impl<
     /// ids in the pair (the one on `node_id`'s end) will be invalid after
     /// calling this function.
     pub fn remove_node(&mut self, node_id: NodeId) -> (Node<NodeData>, Vec<(InputId, OutputId)>) {
-        let mut disconnect_events = vec![];
-
-        self.connections.retain(|i, o| {
-            if self.outputs[*o].node == node_id || self.inputs[i].node == node_id {
-                disconnect_events.push((i, *o));
-                false
-            } else {
-                true
-            }
-        });
+        let disconnect_events = self.disconnect_node(node_id);
 
         // NOTE: Collect is needed because we can't borrow the input ids while
         // we remove them inside the loop.
@@ -288,6 +279,55 @@ impl<
         self.connections.remove(input_id)
     }
 
+    /// Removes the connections to and from a node, and returns them as
+    /// input-output pairs.
+    pub fn disconnect_node(&mut self, node_id: NodeId) -> Vec<(InputId, OutputId)> {
+        let mut disconnect_events = vec![];
+        self.connections.retain(|i, o| {
+            if self.outputs[*o].node == node_id || self.inputs[i].node == node_id {
+                disconnect_events.push((i, *o));
+                false
+            } else {
+                true
+            }
+        });
+        disconnect_events
+    }
+
+    /// Adds a copy of a node, with the same label, user data, parameters and
+    /// input values, but without its connections. Returns the id of the copy.
+    pub fn duplicate_node(&mut self, node_id: NodeId) -> NodeId {
+        let node = &self[node_id];
+        let label = node.label.clone();
+        let user_data = node.user_data.clone();
+        let inputs: Vec<_> = node
+            .inputs
+            .iter()
+            .map(|(name, input)| (name.clone(), self[*input].clone()))
+            .collect();
+        let outputs: Vec<_> = node
+            .outputs
+            .iter()
+            .map(|(name, output)| (name.clone(), self[*output].typ.clone()))
+            .collect();
+
+        let copy = self.add_node(label, user_data);
+        for (name, input) in inputs {
+            self.add_input_param(
+                copy,
+                name,
+                input.typ,
+                input.value,
+                input.kind,
+                input.shown_inline,
+            );
+        }
+        for (name, typ) in outputs {
+            self.add_output_param(copy, name, typ);
+        }
+        copy
+    }
+
     pub fn iter_nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
         self.nodes.iter().map(|(id, _)| id)
     }
@@ -304,6 +344,53 @@ impl<
         self.connections.get(input).copied()
     }
 
+    /// The first input of the node that accepts connections of type `typ` and
+    /// isn't connected yet.
+    pub fn first_free_input(&self, node_id: NodeId, typ: &DataType) -> Option<InputId> {
+        self[node_id].input_ids().find(|input| {
+            let param = self.get_input(*input);
+            param.typ == *typ
+                && !matches!(param.kind, InputParamKind::ConstantOnly)
+                && self.connection(*input).is_none()
+        })
+    }
+
+    /// The first output of the node of type `typ` that isn't connected to
+    /// anything yet.
+    pub fn first_free_output(&self, node_id: NodeId, typ: &DataType) -> Option<OutputId> {
+        self[node_id].output_ids().find(|output| {
+            self.get_output(*output).typ == *typ
+                && !self.connections.values().any(|other| other == output)
+        })
+    }
+
+    /// The names and types of the ports of the node.
+    pub fn node_signature(&self, node_id: NodeId) -> NodeSignature<DataType> {
+        let node = &self[node_id];
+        NodeSignature {
+            inputs: node
+                .inputs
+                .iter()
+                .map(|(name, input)| {
+                    let param = self.get_input(*input);
+                    InputSignature {
+                        name: name.clone(),
+                        typ: param.typ.clone(),
+                        kind: param.kind,
+                    }
+                })
+                .collect(),
+            outputs: node
+                .outputs
+                .iter()
+                .map(|(name, output)| OutputSignature {
+                    name: name.clone(),
+                    typ: self.get_output(*output).typ.clone(),
+                })
+                .collect(),
+        }
+    }
+
     pub fn any_param_type(&self, param: AnyParameterId) -> Result<&DataType, EguiGraphError> {
         match param {
             AnyParameterId::Input(input) => self.inputs.get(input).map(|x| &x.typ),
@@ -0,0 +1,287 @@
+use egui::*;
+
+use super::*;
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserResponse, UserState, CategoryType>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<
+        Response = UserResponse,
+        UserState = UserState,
+        DataType = DataType,
+        ValueType = ValueType,
+    >,
+    UserResponse: UserResponseTrait,
+    ValueType: WidgetValueTrait<Response = UserResponse, UserState = UserState, NodeData = NodeData>
+        + PartialEq,
+    NodeTemplate: NodeTemplateTrait<
+        NodeData = NodeData,
+        DataType = DataType,
+        ValueType = ValueType,
+        UserState = UserState,
+        CategoryType = CategoryType,
+    >,
+    DataType: DataTypeTrait<UserState>,
+    CategoryType: CategoryTrait,
+    UserState: UserStateTrait,
+{
+    /// Lists the ports of the selected node, with the widgets of all its
+    /// inputs, including the ones that aren't shown inline, what they are
+    /// connected to and their types. Meant to be shown in a side panel.
+    ///
+    /// When several nodes are selected, lists the inputs they all have, with
+    /// the same name and type, and that none of them has connected. Changing
+    /// the value of one of them sets it on all the selected nodes. Telling
+    /// a change from a click is why the value type must be `PartialEq`.
+    ///
+    /// The responses of the widgets are returned, wrapped in
+    /// [`NodeResponse::User`].
+    pub fn draw_inspector(
+        &mut self,
+        ui: &mut Ui,
+        user_state: &mut UserState,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        // In the order they are drawn, so the first one doesn't change from
+        // one frame to the next
+        let selected: Vec<NodeId> = self
+            .node_order
+            .iter()
+            .copied()
+            .filter(|node_id| self.selected_nodes.contains(node_id))
+            .collect();
+        match selected.as_slice() {
+            [] => {
+                ui.weak("No node selected");
+                Vec::new()
+            }
+            [node_id] => self.inspect_node(ui, *node_id, user_state),
+            [first, others @ ..] => self.inspect_nodes(ui, *first, others, user_state),
+        }
+    }
+
+    fn inspect_node(
+        &mut self,
+        ui: &mut Ui,
+        node_id: NodeId,
+        user_state: &mut UserState,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let mut responses = Vec::new();
+        ui.heading(&self.graph[node_id].label);
+
+        ui.add_space(4.0);
+        ui.strong("Inputs");
+        let inputs = self.graph[node_id].inputs.clone();
+        Grid::new(("inspector_inputs", node_id))
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (param_name, param_id) in inputs {
+                    ui.horizontal(|ui| {
+                        responses.extend(self.input_widget(ui, &param_name, param_id, user_state));
+                    });
+                    let input = &self.graph[param_id];
+                    let status = match self.graph.connection(param_id) {
+                        Some(output) => format!("from {}", self.output_path(output)),
+                        None if matches!(input.kind, InputParamKind::ConnectionOnly) => {
+                            "not connected".to_owned()
+                        }
+                        None => "constant".to_owned(),
+                    };
+                    ui.weak(format!("{}, {status}", input.typ.name()));
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(4.0);
+        ui.strong("Outputs");
+        Grid::new(("inspector_outputs", node_id))
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (param_name, param_id) in &self.graph[node_id].outputs {
+                    let connections = self
+                        .graph
+                        .iter_connections()
+                        .filter(|(_, output)| output == param_id)
+                        .count();
+                    ui.label(param_name);
+                    ui.weak(format!(
+                        "{}, {connections} connection{}",
+                        self.graph[*param_id].typ.name(),
+                        if connections == 1 { "" } else { "s" }
+                    ));
+                    ui.end_row();
+                }
+            });
+
+        responses
+    }
+
+    fn inspect_nodes(
+        &mut self,
+        ui: &mut Ui,
+        first: NodeId,
+        others: &[NodeId],
+        user_state: &mut UserState,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let mut responses = Vec::new();
+        ui.heading(format!("{} nodes", others.len() + 1));
+
+        // The inputs of the first node that the others have too
+        let free_input = |node_id: NodeId, name: &str, typ: &DataType| {
+            let input = self.graph[node_id].get_input(name).ok()?;
+            (self.graph[input].typ == *typ && self.graph.connection(input).is_none())
+                .then_some(input)
+        };
+        let shared: Vec<(String, InputId, Vec<InputId>)> = self.graph[first]
+            .inputs
+            .iter()
+            .filter_map(|(name, input)| {
+                let typ = &self.graph[*input].typ;
+                free_input(first, name, typ)?;
+                let copies = others
+                    .iter()
+                    .map(|node_id| free_input(*node_id, name, typ))
+                    .collect::<Option<Vec<_>>>()?;
+                Some((name.clone(), *input, copies))
+            })
+            .collect();
+
+        ui.add_space(4.0);
+        if shared.is_empty() {
+            ui.weak("The selected nodes have no inputs in common");
+            return responses;
+        }
+        ui.strong("Shared inputs");
+        Grid::new("inspector_shared_inputs")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (param_name, param_id, copies) in shared {
+                    // The widget shows the value of the first node. The others
+                    // keep their values until it's changed.
+                    let before = self.graph[param_id].value.clone();
+                    ui.horizontal(|ui| {
+                        responses.extend(self.input_widget(ui, &param_name, param_id, user_state));
+                    });
+                    let value = self.graph[param_id].value.clone();
+                    if value != before {
+                        for copy in copies {
+                            self.graph[copy].value = value.clone();
+                        }
+                    }
+                    ui.weak(self.graph[param_id].typ.name());
+                    ui.end_row();
+                }
+            });
+
+        responses
+    }
+
+    /// Draws the widget of an input, like in the node.
+    fn input_widget(
+        &mut self,
+        ui: &mut Ui,
+        param_name: &str,
+        param_id: InputId,
+        user_state: &mut UserState,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        // See `GraphNodeWidget::show_graph_node` for why the value is taken
+        let node_id = self.graph[param_id].node;
+        let mut value = std::mem::take(&mut self.graph[param_id].value);
+        let node_responses = if self.graph.connection(param_id).is_some() {
+            value.value_widget_connected(
+                param_name,
+                node_id,
+                ui,
+                user_state,
+                &self.graph[node_id].user_data,
+            )
+        } else {
+            value.value_widget(
+                param_name,
+                node_id,
+                ui,
+                user_state,
+                &self.graph[node_id].user_data,
+            )
+        };
+        self.graph[param_id].value = value;
+        node_responses.into_iter().map(NodeResponse::User).collect()
+    }
+
+    /// The label of the node of an output, followed by its name.
+    fn output_path(&self, output: OutputId) -> String {
+        let node = &self.graph[self.graph.get_output(output).node];
+        let name = node
+            .outputs
+            .iter()
+            .find(|(_, id)| *id == output)
+            .map(|(name, _)| name.as_str())
+            .unwrap_or_default();
+        format!("{}: {name}", node.label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    pub fn test_multi_edit_only_copies_changes() {
+        let mut editor = TestEditor::default();
+        let template = TestTemplate::new("Scalar add", "Math");
+        let nodes = [1.0, 2.0].map(|value| {
+            let node_id = add_test_node(&mut editor, &template, Pos2::ZERO);
+            let input = editor.graph[node_id].get_input("a").unwrap();
+            editor.graph[input].value = TestValue(value);
+            editor.selected_nodes.insert(node_id);
+            input
+        });
+        let values = |editor: &TestEditor| nodes.map(|input| editor.graph[input].value.0);
+
+        let ctx = Context::default();
+        let frame = |editor: &mut TestEditor, events: Vec<Event>| {
+            run_frame(&ctx, events, |ui| {
+                let _ = editor.draw_inspector(ui, &mut TestState);
+            });
+        };
+        frame(&mut editor, Vec::new());
+        let rect: Rect = ctx
+            .data(|data| data.get_temp(Id::new(VALUE_WIDGET_RECT)))
+            .unwrap();
+
+        // Clicking the field, which also focuses it, changes nothing
+        let pos = rect.center();
+        frame(
+            &mut editor,
+            vec![Event::PointerMoved(pos), primary_button(pos, true)],
+        );
+        frame(&mut editor, vec![primary_button(pos, false)]);
+        frame(&mut editor, Vec::new());
+        assert_eq!(values(&editor), [1.0, 2.0]);
+
+        // Dragging it sets the new value on both nodes
+        let dragged_to = pos + vec2(40.0, 0.0);
+        frame(
+            &mut editor,
+            vec![Event::Key {
+                key: Key::Escape,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers: Modifiers::NONE,
+            }],
+        );
+        frame(&mut editor, vec![primary_button(pos, true)]);
+        for step in 1..=4 {
+            let to = pos + vec2(10.0 * step as f32, 0.0);
+            frame(&mut editor, vec![Event::PointerMoved(to)]);
+        }
+        frame(&mut editor, vec![primary_button(dragged_to, false)]);
+        let [first, second] = values(&editor);
+        assert_ne!(first, 1.0);
+        assert_eq!(first, second);
+    }
+}
@@ -32,16 +32,34 @@ pub use ui_state::*;
 pub mod node_finder;
 pub use node_finder::*;
 
+/// A docked list of the node templates, dragged onto the graph to create nodes
+pub mod palette;
+pub use palette::*;
+
+/// A side panel listing and editing the inputs of the selected nodes
+pub mod inspector;
+
 /// The inner details of the egui implementation. Most egui code lives here.
 pub mod editor_ui;
 pub use editor_ui::*;
 
+/// The shapes of the connections between ports
+pub mod wire;
+pub use wire::*;
+
+/// The colors and sizes used to draw the editor
+pub mod theme;
+pub use theme::*;
+
 /// Several traits that must be implemented by the user to customize the
 /// behavior of this library.
 pub mod traits;
 pub use traits::*;
 
-mod utils;
-
-mod color_hex_utils;
+/// Conversions between egui colors and hex strings, e.g. to build a theme
+/// from a palette
+pub mod color_hex_utils;
 mod scale;
+
+#[cfg(test)]
+mod test_utils;
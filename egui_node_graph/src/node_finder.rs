@@ -1,19 +1,301 @@
-use std::{collections::BTreeMap, marker::PhantomData};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::marker::PhantomData;
 
-use crate::{color_hex_utils::*, CategoryTrait, NodeTemplateIter, NodeTemplateTrait};
+use crate::{
+    AnyParameterId, CategoryTrait, DataTypeTrait, EditorCommandId, GraphTheme, NodeTemplateTrait,
+};
 
 use egui::*;
 
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// How much lower a match on an alias ranks than the same match on a label.
+const ALIAS_PENALTY: i32 = 2;
+/// How much lower a match on a keyword ranks than the same match on a label.
+const KEYWORD_PENALTY: i32 = 3;
+/// How much lower a match on a category ranks than the same match on a label.
+const CATEGORY_PENALTY: i32 = 5;
+/// How much higher a favourite template ranks than the same match on another.
+const FAVOURITE_BONUS: i32 = 3;
+/// How many templates are listed under "Recently used".
+const MAX_RECENT_TEMPLATES: usize = 8;
+/// Starting the query with it lists the editor commands instead of the
+/// templates.
+const COMMAND_PREFIX: char = '>';
+
 #[derive(Clone)]
-#[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
 pub struct NodeFinder<NodeTemplate> {
     pub query: String,
     /// Reset every frame. When set, the node finder will be moved at that position
     pub position: Option<Pos2>,
     pub just_spawned: bool,
+    /// The port a connection was dragged from to open the finder. Only the
+    /// templates that can connect to it are listed, and the created node gets
+    /// connected to it.
+    pub connect_from: Option<AnyParameterId>,
+    /// The index of the row highlighted with the Up and Down keys, which Enter
+    /// activates.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub highlighted: usize,
+    /// The expanded categories, toggled by clicking them or with Tab.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub open_categories: HashSet<String>,
+    /// The templates and what the finder needs to know about them, gathered
+    /// the first time the finder is shown.
+    #[cfg_attr(feature = "persistence", serde(skip, default = "Option::default"))]
+    catalog: Option<Vec<CatalogEntry<NodeTemplate>>>,
+    /// The commands listed in command mode, gathered with the catalog.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    commands: Vec<FinderCommand>,
+    /// The rows listed for the last query, and what they were built from.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    rows: Option<(RowsKey, Vec<FinderRow>)>,
+    /// The scroll offset and height of the list as of the last frame, used
+    /// to keep the highlighted row visible.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    scroll: (f32, f32),
     _phantom: PhantomData<NodeTemplate>,
 }
 
+/// An editor command, as listed by the node finder. See
+/// [`EditorCommandTrait`].
+#[derive(Clone, Debug)]
+pub struct FinderCommand {
+    pub id: EditorCommandId,
+    pub label: String,
+    pub description: Option<String>,
+}
+
+/// What was picked in the node finder.
+#[derive(Clone, Debug)]
+pub enum NodeFinderChoice<NodeTemplate> {
+    Template(NodeTemplate),
+    Command(EditorCommandId),
+}
+
+/// What the node finder remembers from one use to the next: the recently used
+/// and the favourite templates, identified by their
+/// [`NodeTemplateTrait::node_template_id`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct NodeFinderMemory {
+    /// Most recent first.
+    pub recent: Vec<String>,
+    pub favourites: Vec<String>,
+}
+
+impl NodeFinderMemory {
+    pub fn record_use(&mut self, id: &str) {
+        self.recent.retain(|recent| recent != id);
+        self.recent.insert(0, id.to_owned());
+        self.recent.truncate(MAX_RECENT_TEMPLATES);
+    }
+
+    pub fn is_favourite(&self, id: &str) -> bool {
+        self.favourites.iter().any(|favourite| favourite == id)
+    }
+
+    pub fn toggle_favourite(&mut self, id: &str) {
+        if self.is_favourite(id) {
+            self.favourites.retain(|favourite| favourite != id);
+        } else {
+            self.favourites.push(id.to_owned());
+        }
+    }
+}
+
+/// A template of the catalog, with the results of the trait methods the
+/// finder calls to list and search it.
+#[derive(Clone)]
+pub(crate) struct CatalogEntry<NodeTemplate> {
+    pub(crate) kind: NodeTemplate,
+    /// See [`NodeTemplateTrait::node_template_id`].
+    pub(crate) id: String,
+    pub(crate) label: String,
+    category_names: Vec<String>,
+    category_paths: Vec<Vec<String>>,
+    aliases: Vec<String>,
+    keywords: Vec<String>,
+}
+
+/// Everything the rows depend on. The rows are rebuilt when it changes.
+#[derive(Clone, PartialEq)]
+struct RowsKey {
+    query: String,
+    open_categories: Vec<String>,
+    memory: (Vec<String>, Vec<String>),
+    colors: (Color32, Color32),
+    font_id: FontId,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RowKind {
+    /// A template, by index in the catalog.
+    Template(usize),
+    /// A command, by index in the commands.
+    Command(usize),
+    Category,
+    /// The title of a group of rows, which the highlight skips.
+    Title,
+}
+
+/// A line of the node finder list.
+#[derive(Clone)]
+struct FinderRow {
+    kind: RowKind,
+    /// The template label, the full path of a category or the title.
+    label: String,
+    text: WidgetText,
+    /// The category the row toggles with Tab: its own for a header, the
+    /// containing one for a template listed inside a category.
+    category: Option<String>,
+    /// The nesting level, in the category tree.
+    depth: usize,
+}
+
+impl FinderRow {
+    fn title(title: &str) -> Self {
+        FinderRow {
+            kind: RowKind::Title,
+            label: title.to_owned(),
+            text: RichText::new(title).weak().into(),
+            category: None,
+            depth: 0,
+        }
+    }
+
+    /// Whether both rows list the same template, command or category.
+    fn same_entry(&self, other: &FinderRow) -> bool {
+        self.kind == other.kind && self.category == other.category && self.label == other.label
+    }
+}
+
+/// A level of the category tree, see [`CategoryTrait::path`].
+#[derive(Clone, Default)]
+pub(crate) struct CategoryNode {
+    pub(crate) children: BTreeMap<String, CategoryNode>,
+    pub(crate) items: Vec<TreeItem>,
+    /// The best score of the search results below this node.
+    best_score: Option<i32>,
+}
+
+#[derive(Clone)]
+pub(crate) struct TreeItem {
+    /// The index of the template in the catalog.
+    pub(crate) index: usize,
+    label: String,
+    text: WidgetText,
+    score: i32,
+}
+
+impl CategoryNode {
+    /// The tree of all the templates of the `catalog`, in their order.
+    pub(crate) fn from_catalog<NodeTemplate>(catalog: &[CatalogEntry<NodeTemplate>]) -> Self {
+        let mut tree = CategoryNode::default();
+        for (index, entry) in catalog.iter().enumerate() {
+            let item = TreeItem {
+                index,
+                label: entry.label.clone(),
+                text: entry.label.as_str().into(),
+                score: 0,
+            };
+            tree.insert_template(&entry.category_paths, item);
+        }
+        tree
+    }
+
+    /// Inserts the template under each of its category `paths`, or at the
+    /// root when it has none.
+    fn insert_template(&mut self, paths: &[Vec<String>], item: TreeItem) {
+        if paths.is_empty() {
+            self.insert(&[], item);
+            return;
+        }
+        for path in paths {
+            self.insert(path, item.clone());
+        }
+    }
+
+    fn insert(&mut self, path: &[String], item: TreeItem) {
+        self.best_score = self.best_score.max(Some(item.score));
+        match path.split_first() {
+            Some((first, rest)) => self
+                .children
+                .entry(first.clone())
+                .or_default()
+                .insert(rest, item),
+            None => self.items.push(item),
+        }
+    }
+
+    /// Appends the rows of this node's subcategories and templates. When
+    /// `ranked`, everything is expanded and sorted by score, best first.
+    /// Otherwise, subcategories come first, in alphabetical order, and only
+    /// the `open_categories` are expanded.
+    fn flatten(
+        self,
+        path: &str,
+        depth: usize,
+        ranked: bool,
+        open_categories: &HashSet<String>,
+        rows: &mut Vec<FinderRow>,
+    ) {
+        enum Entry {
+            Category(String, CategoryNode),
+            Item(TreeItem),
+        }
+        let mut entries: Vec<Entry> = self
+            .children
+            .into_iter()
+            .map(|(name, child)| Entry::Category(name, child))
+            .chain(self.items.into_iter().map(Entry::Item))
+            .collect();
+        if ranked {
+            // Stable, so ties keep their alphabetical order
+            entries.sort_by_key(|entry| {
+                std::cmp::Reverse(match entry {
+                    Entry::Category(_, child) => child.best_score,
+                    Entry::Item(item) => Some(item.score),
+                })
+            });
+        }
+
+        let parent = (depth > 0).then(|| path.to_owned());
+        for entry in entries {
+            match entry {
+                Entry::Category(name, child) => {
+                    let child_path = if path.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{path}/{name}")
+                    };
+                    let open = ranked || open_categories.contains(&child_path);
+                    let arrow = if open { "⏷" } else { "⏵" };
+                    rows.push(FinderRow {
+                        kind: RowKind::Category,
+                        label: child_path.clone(),
+                        text: format!("{arrow} {name}").into(),
+                        category: (!ranked).then(|| child_path.clone()),
+                        depth,
+                    });
+                    if open {
+                        child.flatten(&child_path, depth + 1, ranked, open_categories, rows);
+                    }
+                }
+                Entry::Item(item) => rows.push(FinderRow {
+                    kind: RowKind::Template(item.index),
+                    label: item.label,
+                    text: item.text,
+                    category: if ranked { None } else { parent.clone() },
+                    depth,
+                }),
+            }
+        }
+    }
+}
+
 impl<NodeTemplate, NodeData, UserState, CategoryType> NodeFinder<NodeTemplate>
 where
     NodeTemplate:
@@ -25,127 +307,815 @@ where
             query: "".into(),
             position: Some(pos),
             just_spawned: true,
+            connect_from: None,
+            highlighted: 0,
+            open_categories: Default::default(),
+            catalog: None,
+            commands: Vec::new(),
+            rows: None,
+            scroll: (0.0, 0.0),
             _phantom: Default::default(),
         }
     }
 
-    /// Shows the node selector panel with a search bar. Returns whether a node
-    /// archetype was selected and, in that case, the finder should be hidden on
-    /// the next frame.
+    /// Like [`NodeFinder::new_at`], for a connection dragged from `port` and
+    /// released over the empty canvas.
+    pub fn new_connecting(pos: Pos2, port: AnyParameterId) -> Self {
+        NodeFinder {
+            connect_from: Some(port),
+            ..Self::new_at(pos)
+        }
+    }
+
+    /// Shows the node selector panel with a search bar. Returns the node
+    /// archetype or the command that was picked, if any. In that case, the
+    /// finder should be hidden on the next frame.
+    ///
+    /// While the search bar is empty, the favourite and recently used templates
+    /// are listed first, and then all the templates by category. Otherwise,
+    /// the templates matching the query are listed best first, see
+    /// [`fuzzy_match`]. Up and Down move the highlighted row, Enter picks it,
+    /// and Tab expands or collapses its category.
+    ///
+    /// When the query starts with `>`, the `commands` are listed instead, and
+    /// the rest of the query is matched against their labels.
+    ///
+    /// `templates` and `commands` are only called the first time the finder
+    /// is shown, and what they return is listed until the finder is closed.
+    /// The rows are only rebuilt when the query changes, and only the visible
+    /// ones are laid out.
     pub fn show(
         &mut self,
         ui: &mut Ui,
-        all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
+        templates: impl FnOnce(&mut UserState) -> Vec<NodeTemplate>,
+        commands: impl FnOnce(&mut UserState) -> Vec<FinderCommand>,
         user_state: &mut UserState,
-    ) -> Option<NodeTemplate> {
-        let background_color;
-        let text_color;
-
-        if ui.visuals().dark_mode {
-            background_color = color_from_hex("#3f3f3f").unwrap();
-            text_color = color_from_hex("#fefefe").unwrap();
-        } else {
-            background_color = color_from_hex("#fefefe").unwrap();
-            text_color = color_from_hex("#3f3f3f").unwrap();
-        }
-
-        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, text_color);
+        theme: &GraphTheme,
+        memory: &mut NodeFinderMemory,
+    ) -> Option<NodeFinderChoice<NodeTemplate>> {
+        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, theme.finder_text);
 
         let frame = Frame::dark_canvas(ui.style())
-            .fill(background_color)
+            .fill(theme.finder_background)
             .inner_margin(vec2(5.0, 5.0));
 
-        // The archetype that will be returned.
+        self.gather_catalog(templates, commands, user_state);
+
+        // The archetype or command that will be returned.
         let mut submitted_archetype = None;
         frame.show(ui, |ui| {
             ui.vertical(|ui| {
-                let resp = ui.text_edit_singleline(&mut self.query);
+                // Taken before the text edit gets to move its cursor with them
+                let (up_pressed, down_pressed, tab_pressed) = ui.input_mut(|i| {
+                    (
+                        i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                        i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                        i.consume_key(Modifiers::NONE, Key::Tab),
+                    )
+                });
+                let resp = ui.add(TextEdit::singleline(&mut self.query).lock_focus(true));
                 if self.just_spawned {
                     resp.request_focus();
                     self.just_spawned = false;
                 }
-                let update_open = resp.changed();
 
-                let mut query_submit = resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                let query_submit = resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
 
                 let max_height = ui.input(|i| i.screen_rect.height() * 0.5);
                 let scroll_area_width = resp.rect.width() - 30.0;
+                let font_id = TextStyle::Button.resolve(ui.style());
 
-                let all_kinds = all_kinds.all_kinds();
-                let mut categories: BTreeMap<String, Vec<&NodeTemplate>> = Default::default();
-                let mut orphan_kinds = Vec::new();
+                self.update_rows(theme, memory, &font_id);
+                if resp.changed() {
+                    // The best search result, rather than its category
+                    self.highlighted = self.first_template_row();
+                }
 
-                for kind in &all_kinds {
-                    let kind_categories = kind.node_finder_categories(user_state);
+                /* Keyboard navigation */
+                let moved = up_pressed || down_pressed;
+                if up_pressed {
+                    self.move_highlight(-1);
+                }
+                if down_pressed {
+                    self.move_highlight(1);
+                }
+                self.move_highlight(0);
+                if tab_pressed {
+                    if let Some(category) =
+                        self.row(self.highlighted).and_then(|r| r.category.clone())
+                    {
+                        self.toggle_category(&category);
+                        self.update_rows(theme, memory, &font_id);
+                        // Keep the highlight on the header of the category
+                        self.highlighted = self
+                            .rows()
+                            .iter()
+                            .position(|row| row.kind == RowKind::Category && row.label == category)
+                            .unwrap_or(0);
+                    }
+                }
 
-                    if kind_categories.is_empty() {
-                        orphan_kinds.push(kind);
-                    } else {
-                        for category in kind_categories {
-                            categories.entry(category.name()).or_default().push(kind);
-                        }
+                let mut activated = query_submit.then_some(self.highlighted);
+                let mut toggled_favourite = None;
+
+                let row_height = ui.spacing().interact_size.y;
+                let mut scroll_area = ScrollArea::vertical().max_height(max_height);
+                if moved {
+                    // Rows out of view aren't laid out, so they can't be
+                    // scrolled to. Compute the offset that shows the row.
+                    let row_step = row_height + ui.spacing().item_spacing.y;
+                    let row_top = self.highlighted as f32 * row_step;
+                    let (offset, height) = self.scroll;
+                    if row_top < offset {
+                        scroll_area = scroll_area.vertical_scroll_offset(row_top);
+                    } else if row_top + row_step > offset + height {
+                        scroll_area =
+                            scroll_area.vertical_scroll_offset(row_top + row_step - height);
                     }
                 }
 
-                Frame::default()
+                let catalog = self.catalog.as_ref().unwrap();
+                let commands = &self.commands;
+                let rows = &self.rows.as_ref().unwrap().1;
+                let highlighted = self.highlighted;
+                let output = Frame::default()
                     .inner_margin(vec2(10.0, 10.0))
                     .show(ui, |ui| {
-                        ScrollArea::vertical()
-                            .max_height(max_height)
-                            .show(ui, |ui| {
-                                ui.set_width(scroll_area_width);
-                                for (category, kinds) in categories {
-                                    let filtered_kinds: Vec<_> = kinds
-                                        .into_iter()
-                                        .map(|kind| {
-                                            let kind_name =
-                                                kind.node_finder_label(user_state).to_string();
-                                            (kind, kind_name)
-                                        })
-                                        .filter(|(_kind, kind_name)| {
-                                            kind_name
-                                                .to_lowercase()
-                                                .contains(self.query.to_lowercase().as_str())
-                                        })
-                                        .collect();
-
-                                    if !filtered_kinds.is_empty() {
-                                        let default_open = !self.query.is_empty();
-
-                                        CollapsingHeader::new(&category)
-                                            .default_open(default_open)
-                                            .open(update_open.then_some(default_open))
-                                            .show(ui, |ui| {
-                                                for (kind, kind_name) in filtered_kinds {
-                                                    if ui
-                                                        .selectable_label(false, kind_name)
-                                                        .clicked()
-                                                    {
-                                                        submitted_archetype = Some(kind.clone());
-                                                    } else if query_submit {
-                                                        submitted_archetype = Some(kind.clone());
-                                                        query_submit = false;
-                                                    }
-                                                }
+                        scroll_area.show_rows(ui, row_height, rows.len(), |ui, range| {
+                            ui.set_width(scroll_area_width);
+                            for index in range {
+                                let row = &rows[index];
+                                ui.horizontal(|ui| {
+                                    ui.set_min_height(row_height);
+                                    ui.add_space(ui.spacing().indent * row.depth as f32);
+                                    if row.kind == RowKind::Title {
+                                        ui.label(row.text.clone());
+                                        return;
+                                    }
+                                    if let RowKind::Template(template) = row.kind {
+                                        let id = &catalog[template].id;
+                                        let star = if memory.is_favourite(id) {
+                                            "★"
+                                        } else {
+                                            "☆"
+                                        };
+                                        if ui
+                                            .small_button(star)
+                                            .on_hover_text("Favourite")
+                                            .clicked()
+                                        {
+                                            toggled_favourite = Some(id.clone());
+                                        }
+                                    }
+                                    let mut resp =
+                                        ui.selectable_label(index == highlighted, row.text.clone());
+                                    match row.kind {
+                                        RowKind::Template(template) => {
+                                            resp = resp.on_hover_ui(|ui| {
+                                                template_tooltip(
+                                                    ui,
+                                                    &catalog[template].kind,
+                                                    user_state,
+                                                )
                                             });
+                                        }
+                                        RowKind::Command(command) => {
+                                            if let Some(description) =
+                                                &commands[command].description
+                                            {
+                                                resp = resp.on_hover_text(description);
+                                            }
+                                        }
+                                        RowKind::Category | RowKind::Title => {}
                                     }
-                                }
-
-                                for kind in orphan_kinds {
-                                    let kind_name = kind.node_finder_label(user_state).to_string();
-
-                                    if ui.selectable_label(false, kind_name).clicked() {
-                                        submitted_archetype = Some(kind.clone());
-                                    } else if query_submit {
-                                        submitted_archetype = Some(kind.clone());
-                                        query_submit = false;
+                                    if resp.clicked() {
+                                        activated = Some(index);
                                     }
-                                }
-                            });
-                    });
+                                });
+                            }
+                        })
+                    })
+                    .inner;
+                self.scroll = (output.state.offset.y, output.inner_rect.height());
+
+                if let Some(id) = toggled_favourite {
+                    memory.toggle_favourite(&id);
+                }
+                let activated = activated.and_then(|index| self.row(index).cloned());
+                match activated {
+                    Some(FinderRow {
+                        kind: RowKind::Template(template),
+                        ..
+                    }) => {
+                        let entry = &self.catalog.as_ref().unwrap()[template];
+                        memory.record_use(&entry.id);
+                        submitted_archetype = Some(NodeFinderChoice::Template(entry.kind.clone()));
+                    }
+                    Some(FinderRow {
+                        kind: RowKind::Command(command),
+                        ..
+                    }) => {
+                        let id = self.commands[command].id.clone();
+                        submitted_archetype = Some(NodeFinderChoice::Command(id));
+                    }
+                    Some(FinderRow {
+                        kind: RowKind::Category,
+                        category: Some(category),
+                        ..
+                    }) => {
+                        self.toggle_category(&category);
+                        self.highlighted = self
+                            .rows()
+                            .iter()
+                            .position(|row| row.label == category && row.kind == RowKind::Category)
+                            .unwrap_or(0);
+                        // Enter made the text edit lose focus
+                        resp.request_focus();
+                    }
+                    _ => {}
+                }
             });
         });
 
         submitted_archetype
     }
+
+    fn gather_catalog(
+        &mut self,
+        templates: impl FnOnce(&mut UserState) -> Vec<NodeTemplate>,
+        commands: impl FnOnce(&mut UserState) -> Vec<FinderCommand>,
+        user_state: &mut UserState,
+    ) {
+        if self.catalog.is_none() {
+            let templates = templates(user_state);
+            self.catalog = Some(build_catalog(templates, user_state));
+            self.commands = commands(user_state);
+            self.rows = None;
+        }
+    }
+
+    fn toggle_category(&mut self, category: &str) {
+        if !self.open_categories.remove(category) {
+            self.open_categories.insert(category.to_owned());
+        }
+    }
+
+    fn rows(&self) -> &[FinderRow] {
+        self.rows
+            .as_ref()
+            .map(|(_, rows)| rows.as_slice())
+            .unwrap_or(&[])
+    }
+
+    fn row(&self, index: usize) -> Option<&FinderRow> {
+        self.rows().get(index)
+    }
+
+    fn first_template_row(&self) -> usize {
+        self.rows()
+            .iter()
+            .position(|row| matches!(row.kind, RowKind::Template(_) | RowKind::Command(_)))
+            .unwrap_or(0)
+    }
+
+    /// Moves the highlight by `step` rows, skipping titles, and keeps it in
+    /// the list.
+    fn move_highlight(&mut self, step: isize) {
+        let rows = self.rows();
+        if rows.is_empty() {
+            self.highlighted = 0;
+            return;
+        }
+        let mut index = self.highlighted.min(rows.len() - 1);
+        let mut target = index as isize + step;
+        while (0..rows.len() as isize).contains(&target) {
+            if rows[target as usize].kind != RowKind::Title {
+                index = target as usize;
+                break;
+            }
+            target += step.signum();
+            if step == 0 {
+                target += 1;
+            }
+        }
+        self.highlighted = index;
+    }
+
+    /// Rebuilds the rows if the query or anything else they depend on changed.
+    /// The highlight stays on the same entry, when it's still listed.
+    fn update_rows(&mut self, theme: &GraphTheme, memory: &NodeFinderMemory, font_id: &FontId) {
+        let mut open_categories: Vec<String> = self.open_categories.iter().cloned().collect();
+        open_categories.sort();
+        let key = RowsKey {
+            query: self.query.clone(),
+            open_categories,
+            memory: (memory.recent.clone(), memory.favourites.clone()),
+            colors: (theme.finder_text, theme.finder_highlight),
+            font_id: font_id.clone(),
+        };
+        if self
+            .rows
+            .as_ref()
+            .is_some_and(|(old_key, _)| *old_key == key)
+        {
+            return;
+        }
+        let highlighted = self.row(self.highlighted).cloned();
+        let rows = self.build_rows(theme, memory, font_id);
+        if let Some(highlighted) = highlighted {
+            // A template can be listed more than once, keep the closest
+            if let Some((index, _)) = rows
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| row.same_entry(&highlighted))
+                .min_by_key(|(index, _)| index.abs_diff(self.highlighted))
+            {
+                self.highlighted = index;
+            }
+        }
+        self.rows = Some((key, rows));
+    }
+
+    /// The rows to list: the search results when there is a query, or else the
+    /// favourite and recent templates followed by the category tree.
+    fn build_rows(
+        &self,
+        theme: &GraphTheme,
+        memory: &NodeFinderMemory,
+        font_id: &FontId,
+    ) -> Vec<FinderRow> {
+        let catalog = self.catalog.as_deref().unwrap_or(&[]);
+        let mut rows = Vec::new();
+
+        if let Some(query) = self.query.strip_prefix(COMMAND_PREFIX) {
+            let query = query.trim();
+            let mut results: Vec<_> = self
+                .commands
+                .iter()
+                .enumerate()
+                .filter_map(|(index, command)| {
+                    if query.is_empty() {
+                        return Some((index, 0, Vec::new()));
+                    }
+                    fuzzy_match(query, &command.label).map(|m| (index, m.score, m.indices))
+                })
+                .collect();
+            // Stable, so the commands keep their order when there is no query
+            results.sort_by_key(|(_, score, _)| std::cmp::Reverse(*score));
+            for (index, _, matched) in results {
+                let label = &self.commands[index].label;
+                rows.push(FinderRow {
+                    kind: RowKind::Command(index),
+                    label: label.clone(),
+                    text: result_layout_job(label, &matched, font_id.clone(), theme).into(),
+                    category: None,
+                    depth: 0,
+                });
+            }
+            return rows;
+        }
+
+        if !self.query.is_empty() {
+            let mut tree = CategoryNode::default();
+            for result in self.ranked_results(catalog, memory) {
+                let entry = &catalog[result.index];
+                let item = TreeItem {
+                    index: result.index,
+                    label: entry.label.clone(),
+                    text: result_layout_job(&entry.label, &result.matched, font_id.clone(), theme)
+                        .into(),
+                    score: result.score,
+                };
+                tree.insert_template(&entry.category_paths, item);
+            }
+            tree.flatten("", 0, true, &self.open_categories, &mut rows);
+            return rows;
+        }
+
+        let by_id: HashMap<&str, usize> = catalog
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (entry.id.as_str(), index))
+            .collect();
+        for (title, group) in [
+            ("Favourites", &memory.favourites),
+            ("Recently used", &memory.recent),
+        ] {
+            let mut group_rows = group
+                .iter()
+                .filter_map(|id| by_id.get(id.as_str()))
+                .map(|index| FinderRow {
+                    kind: RowKind::Template(*index),
+                    label: catalog[*index].label.clone(),
+                    text: catalog[*index].label.as_str().into(),
+                    category: None,
+                    depth: 0,
+                })
+                .peekable();
+            if group_rows.peek().is_some() {
+                rows.push(FinderRow::title(title));
+                rows.extend(group_rows);
+            }
+        }
+
+        let tree = CategoryNode::from_catalog(catalog);
+        if !rows.is_empty() {
+            rows.push(FinderRow::title("All"));
+        }
+        tree.flatten("", 0, false, &self.open_categories, &mut rows);
+
+        rows
+    }
+
+    /// The templates matching the query, best matches first. Templates are
+    /// matched by label, and then by alias, keyword and category name.
+    /// Favourites get a small bonus.
+    fn ranked_results(
+        &self,
+        catalog: &[CatalogEntry<NodeTemplate>],
+        memory: &NodeFinderMemory,
+    ) -> Vec<SearchResult> {
+        let mut results: Vec<_> = catalog
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let label_match = fuzzy_match(&self.query, &entry.label);
+                let alias_score = entry
+                    .aliases
+                    .iter()
+                    .filter_map(|alias| fuzzy_match(&self.query, alias))
+                    .map(|m| m.score - ALIAS_PENALTY);
+                let keyword_score = entry
+                    .keywords
+                    .iter()
+                    .filter_map(|keyword| fuzzy_match(&self.query, keyword))
+                    .map(|m| m.score - KEYWORD_PENALTY);
+                let category_score = entry
+                    .category_names
+                    .iter()
+                    .filter_map(|category| fuzzy_match(&self.query, category))
+                    .map(|m| m.score - CATEGORY_PENALTY);
+                let other_score = alias_score.chain(keyword_score).chain(category_score).max();
+
+                let (score, matched) = match (label_match, other_score) {
+                    (Some(m), Some(other)) if other > m.score => (other, Vec::new()),
+                    (Some(m), _) => (m.score, m.indices),
+                    (None, Some(other)) => (other, Vec::new()),
+                    (None, None) => return None,
+                };
+                let score = if memory.is_favourite(&entry.id) {
+                    score + FAVOURITE_BONUS
+                } else {
+                    score
+                };
+                Some(SearchResult {
+                    index,
+                    score,
+                    matched,
+                })
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| catalog[a.index].label.cmp(&catalog[b.index].label))
+        });
+        results
+    }
+}
+
+/// Calls the trait methods the finder needs, once per template.
+pub(crate) fn build_catalog<NodeTemplate: NodeTemplateTrait>(
+    all_kinds: Vec<NodeTemplate>,
+    user_state: &mut NodeTemplate::UserState,
+) -> Vec<CatalogEntry<NodeTemplate>>
+where
+    NodeTemplate::CategoryType: CategoryTrait,
+{
+    all_kinds
+        .into_iter()
+        .map(|kind| {
+            let categories = kind.node_finder_categories(user_state);
+            CatalogEntry {
+                id: kind.node_template_id(user_state).into_owned(),
+                label: kind.node_finder_label(user_state).into_owned(),
+                category_names: categories.iter().map(|category| category.name()).collect(),
+                category_paths: categories.iter().map(|category| category.path()).collect(),
+                aliases: kind.node_finder_aliases(user_state),
+                keywords: kind.keywords(user_state),
+                kind,
+            }
+        })
+        .collect()
+}
+
+/// A template matching the node finder query.
+struct SearchResult {
+    /// The index of the template in the catalog.
+    index: usize,
+    score: i32,
+    /// The char indices of the label matching the query. Empty when the
+    /// template matched by alias, keyword or category.
+    matched: Vec<usize>,
+}
+
+/// The label of a search result with the `matched` chars highlighted.
+fn result_layout_job(
+    label: &str,
+    matched: &[usize],
+    font_id: FontId,
+    theme: &GraphTheme,
+) -> text::LayoutJob {
+    let mut job = text::LayoutJob::default();
+    let format = |highlighted: bool| {
+        let color = if highlighted {
+            theme.finder_highlight
+        } else {
+            theme.finder_text
+        };
+        TextFormat::simple(font_id.clone(), color)
+    };
+
+    // Group the chars in runs of the same highlighting
+    let mut run = String::new();
+    let mut run_highlighted = false;
+    for (index, c) in label.chars().enumerate() {
+        let highlighted = matched.contains(&index);
+        if highlighted != run_highlighted && !run.is_empty() {
+            job.append(&std::mem::take(&mut run), 0.0, format(run_highlighted));
+        }
+        run_highlighted = highlighted;
+        run.push(c);
+    }
+    job.append(&run, 0.0, format(run_highlighted));
+    job
+}
+
+/// The description and ports of a template, shown when hovering it.
+pub(crate) fn template_tooltip<NodeTemplate: NodeTemplateTrait>(
+    ui: &mut Ui,
+    kind: &NodeTemplate,
+    user_state: &mut NodeTemplate::UserState,
+) {
+    ui.strong(kind.node_finder_label(user_state));
+    if let Some(description) = kind.description(user_state) {
+        ui.label(description);
+    }
+
+    let signature = kind.node_signature(user_state);
+    let port_list = |ui: &mut Ui, title: &str, ports: Vec<(String, String)>| {
+        if ports.is_empty() {
+            return;
+        }
+        ui.add_space(4.0);
+        ui.weak(title);
+        for (name, typ) in ports {
+            ui.label(format!("{name}: {typ}"));
+        }
+    };
+    port_list(
+        ui,
+        "Inputs",
+        signature
+            .inputs
+            .iter()
+            .map(|input| (input.name.clone(), input.typ.name().into_owned()))
+            .collect(),
+    );
+    port_list(
+        ui,
+        "Outputs",
+        signature
+            .outputs
+            .iter()
+            .map(|output| (output.name.clone(), output.typ.name().into_owned()))
+            .collect(),
+    );
+}
+
+/// The result of [`fuzzy_match`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Higher is better. Only meaningful to compare matches of the same query.
+    pub score: i32,
+    /// The char indices of the candidate matching the chars of the query.
+    pub indices: Vec<usize>,
+}
+
+/// Case-insensitive subsequence matching: every char of `query` must appear in
+/// `candidate`, in order. Matches at word starts and runs of consecutive chars
+/// score higher, gaps score lower. Returns `None` when `query` is empty or
+/// isn't a subsequence of `candidate`.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().collect();
+    let candidate: Vec<char> = candidate.chars().collect();
+    let eq = |a: char, b: char| a.to_lowercase().eq(b.to_lowercase());
+    let is_word_start = |index: usize| {
+        index == 0 || {
+            let (prev, c) = (candidate[index - 1], candidate[index]);
+            !prev.is_alphanumeric() || (prev.is_lowercase() && c.is_uppercase())
+        }
+    };
+    let first = *query.first()?;
+
+    // The first char can match in several places. Greedily match the rest
+    // from each of them, and keep the best.
+    let mut best: Option<FuzzyMatch> = None;
+    for start in (0..candidate.len()).filter(|i| eq(candidate[*i], first)) {
+        let mut indices = vec![start];
+        for q in &query[1..] {
+            let next = indices.last().unwrap() + 1;
+            match (next..candidate.len()).find(|i| eq(candidate[*i], *q)) {
+                Some(index) => indices.push(index),
+                None => break,
+            }
+        }
+        if indices.len() < query.len() {
+            // Later starts can't match more chars
+            break;
+        }
+
+        let mut score = -(start.min(5) as i32);
+        for (n, index) in indices.iter().enumerate() {
+            score += 1;
+            if is_word_start(*index) {
+                score += 6;
+            }
+            if n > 0 {
+                let gap = index - indices[n - 1] - 1;
+                if gap == 0 {
+                    score += 4;
+                } else {
+                    score -= gap.min(3) as i32;
+                }
+            }
+        }
+        if query.len() == candidate.len() {
+            score += 20;
+        }
+        if best.as_ref().is_none_or(|best| score > best.score) {
+            best = Some(FuzzyMatch { score, indices });
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const TEMPLATES: [TestTemplate; 3] = [
+        TestTemplate::new("Scalar add", "Math/Scalar"),
+        TestTemplate::new("Scalar subtract", "Math/Scalar"),
+        TestTemplate::new("Vector add", "Math/Vector"),
+    ];
+
+    fn test_finder() -> NodeFinder<TestTemplate> {
+        let mut finder = NodeFinder::new_at(Pos2::ZERO);
+        finder.gather_catalog(|_| TEMPLATES.to_vec(), |_| Vec::new(), &mut TestState);
+        finder
+    }
+
+    fn row_labels(finder: &NodeFinder<TestTemplate>) -> Vec<&str> {
+        finder.rows().iter().map(|row| row.label.as_str()).collect()
+    }
+
+    #[test]
+    pub fn test_fuzzy_match() {
+        assert_eq!(
+            fuzzy_match("vadd", "Vector add").unwrap().indices,
+            vec![0, 7, 8, 9]
+        );
+        assert_eq!(fuzzy_match("xyz", "Vector add"), None);
+        assert_eq!(fuzzy_match("", "Vector add"), None);
+
+        // Word starts beat the first occurrence
+        let word_start = fuzzy_match("ad", "Scalar add").unwrap();
+        assert_eq!(word_start.indices, vec![7, 8]);
+        let mid_word = fuzzy_match("ad", "Shadow").unwrap();
+        assert!(word_start.score > mid_word.score);
+    }
+
+    #[test]
+    pub fn test_keyword_match() {
+        let mut finder = NodeFinder::new_at(Pos2::ZERO);
+        let templates = vec![
+            TestTemplate::new("Scalar subtract", "Math").with_keywords(&["minus"]),
+            TestTemplate::new("Minus", "Math"),
+            TestTemplate::new("Scalar add", "Math"),
+        ];
+        finder.gather_catalog(|_| templates, |_| Vec::new(), &mut TestState);
+        finder.query = "minus".to_owned();
+
+        // Found by its keyword, below the template matching by label
+        let catalog = finder.catalog.as_deref().unwrap();
+        let results = finder.ranked_results(catalog, &NodeFinderMemory::default());
+        let labels: Vec<&str> = results
+            .iter()
+            .map(|result| catalog[result.index].label.as_str())
+            .collect();
+        assert_eq!(labels, ["Minus", "Scalar subtract"]);
+        assert!(results[1].matched.is_empty());
+    }
+
+    #[test]
+    pub fn test_catalog_is_gathered_once() {
+        let mut finder = NodeFinder::new_at(Pos2::ZERO);
+        let mut calls = 0;
+        for _ in 0..2 {
+            let templates = |_: &mut TestState| {
+                calls += 1;
+                TEMPLATES.to_vec()
+            };
+            finder.gather_catalog(templates, |_| Vec::new(), &mut TestState);
+        }
+        assert_eq!(calls, 1);
+        assert_eq!(finder.catalog.as_ref().map(Vec::len), Some(3));
+    }
+
+    #[test]
+    pub fn test_rows_keep_highlight() {
+        let (theme, font_id) = (GraphTheme::DARK, FontId::default());
+        let mut memory = NodeFinderMemory::default();
+        let mut finder = test_finder();
+        finder.update_rows(&theme, &memory, &font_id);
+        assert_eq!(row_labels(&finder), ["Math"]);
+
+        finder.toggle_category("Math");
+        finder.toggle_category("Math/Scalar");
+        finder.update_rows(&theme, &memory, &font_id);
+        assert_eq!(
+            row_labels(&finder),
+            [
+                "Math",
+                "Math/Scalar",
+                "Scalar add",
+                "Scalar subtract",
+                "Math/Vector"
+            ]
+        );
+
+        // Favouriting the highlighted template lists it first, the highlight
+        // stays on its row in the tree
+        finder.highlighted = 3;
+        memory.toggle_favourite("Scalar subtract");
+        finder.update_rows(&theme, &memory, &font_id);
+        assert_eq!(
+            row_labels(&finder),
+            [
+                "Favourites",
+                "Scalar subtract",
+                "All",
+                "Math",
+                "Math/Scalar",
+                "Scalar add",
+                "Scalar subtract",
+                "Math/Vector"
+            ]
+        );
+        assert_eq!(finder.highlighted, 6);
+
+        finder.query = "vadd".to_owned();
+        finder.update_rows(&theme, &memory, &font_id);
+        assert_eq!(row_labels(&finder), ["Math", "Math/Vector", "Vector add"]);
+    }
+
+    #[test]
+    pub fn test_command_mode() {
+        let (theme, font_id) = (GraphTheme::DARK, FontId::default());
+        let memory = NodeFinderMemory::default();
+        let commands =
+            ["Select all", "Select upstream", "Zoom to fit"].map(|label| FinderCommand {
+                id: EditorCommandId::User(label.to_owned()),
+                label: label.to_owned(),
+                description: None,
+            });
+        let mut finder = NodeFinder::new_at(Pos2::ZERO);
+        finder.gather_catalog(
+            |_| TEMPLATES.to_vec(),
+            |_| commands.to_vec(),
+            &mut TestState,
+        );
+        let is_command = |row: &FinderRow| matches!(row.kind, RowKind::Command(_));
+
+        // The prefix alone lists all the commands, in order
+        finder.query = ">".to_owned();
+        finder.update_rows(&theme, &memory, &font_id);
+        assert_eq!(
+            row_labels(&finder),
+            ["Select all", "Select upstream", "Zoom to fit"]
+        );
+
+        finder.query = "> sel up".to_owned();
+        finder.update_rows(&theme, &memory, &font_id);
+        assert_eq!(row_labels(&finder), ["Select upstream"]);
+        assert!(finder.rows().iter().all(is_command));
+
+        // Without the prefix, only templates are listed
+        finder.query = "add".to_owned();
+        finder.update_rows(&theme, &memory, &font_id);
+        assert!(!finder.rows().is_empty());
+        assert!(!finder.rows().iter().any(is_command));
+    }
 }
@@ -0,0 +1,112 @@
+use egui::*;
+
+use super::*;
+
+/// The drag and drop payload of a [`GraphEditorState::draw_node_palette`]
+/// entry: the [`NodeTemplateTrait::node_template_id`] of the template. The
+/// graph editor creates the node where it is dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodePalettePayload(pub String);
+
+/// What [`GraphEditorState::draw_node_palette`] keeps from one frame to the
+/// next.
+#[derive(Clone)]
+pub struct NodePalette<NodeTemplate> {
+    /// The templates and their category tree, gathered the first time the
+    /// palette is shown.
+    catalog: Option<(Vec<CatalogEntry<NodeTemplate>>, CategoryNode)>,
+}
+
+impl<NodeTemplate> Default for NodePalette<NodeTemplate> {
+    fn default() -> Self {
+        NodePalette { catalog: None }
+    }
+}
+
+impl<NodeTemplate> NodePalette<NodeTemplate> {
+    /// Gathers the templates again the next time the palette is shown, e.g.
+    /// after the ones passed to it changed.
+    pub fn reload(&mut self) {
+        self.catalog = None;
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserResponse, UserState, CategoryType>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<
+        Response = UserResponse,
+        UserState = UserState,
+        DataType = DataType,
+        ValueType = ValueType,
+    >,
+    UserResponse: UserResponseTrait,
+    ValueType:
+        WidgetValueTrait<Response = UserResponse, UserState = UserState, NodeData = NodeData>,
+    NodeTemplate: NodeTemplateTrait<
+        NodeData = NodeData,
+        DataType = DataType,
+        ValueType = ValueType,
+        UserState = UserState,
+        CategoryType = CategoryType,
+    >,
+    DataType: DataTypeTrait<UserState>,
+    CategoryType: CategoryTrait,
+    UserState: UserStateTrait,
+{
+    /// Lists the templates in the category tree of the node finder, see
+    /// [`CategoryTrait::path`]. Dragging an entry onto the graph editor
+    /// creates the node where it is dropped. Can be shown in any panel. The
+    /// dropped template is found by its [`NodeTemplateTrait::node_template_id`]
+    /// among the ones passed to `draw_graph_editor`.
+    ///
+    /// `all_kinds` is only gathered the first time the palette is shown, see
+    /// [`NodePalette::reload`].
+    pub fn draw_node_palette(
+        &mut self,
+        ui: &mut Ui,
+        all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
+        user_state: &mut UserState,
+    ) {
+        let (catalog, tree) = self.node_palette.catalog.get_or_insert_with(|| {
+            let catalog = build_catalog(all_kinds.all_kinds(), user_state);
+            let tree = CategoryNode::from_catalog(&catalog);
+            (catalog, tree)
+        });
+
+        let mut entry = |ui: &mut Ui, id: Id, index: usize| {
+            let entry = &catalog[index];
+            let payload = NodePalettePayload(entry.id.clone());
+            ui.dnd_drag_source(id.with(index), payload, |ui| {
+                ui.add(Label::new(&entry.label).selectable(false));
+            })
+            .response
+            .on_hover_ui(|ui| template_tooltip(ui, &entry.kind, user_state));
+        };
+
+        ScrollArea::vertical().show(ui, |ui| {
+            let id = ui.id().with("node_palette");
+            show_category(ui, id, tree, &mut entry);
+        });
+    }
+}
+
+/// Shows the subcategories of `category` as collapsing headers, followed by
+/// its templates.
+fn show_category(
+    ui: &mut Ui,
+    id: Id,
+    category: &CategoryNode,
+    entry: &mut impl FnMut(&mut Ui, Id, usize),
+) {
+    for (name, child) in &category.children {
+        let id = id.with(name);
+        CollapsingHeader::new(name.as_str())
+            .id_salt(id)
+            .default_open(true)
+            .show(ui, |ui| show_category(ui, id, child, entry));
+    }
+    for item in &category.items {
+        entry(ui, id, item.index);
+    }
+}
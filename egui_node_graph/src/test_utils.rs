@@ -0,0 +1,220 @@
+//! Minimal implementations of the user traits, for the tests.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use egui::*;
+
+use super::*;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TestType {
+    Scalar,
+}
+
+impl DataTypeTrait<TestState> for TestType {
+    fn data_type_color(&self, _user_state: &mut TestState) -> Color32 {
+        Color32::GRAY
+    }
+
+    fn name(&self) -> Cow<'_, str> {
+        match self {
+            TestType::Scalar => Cow::Borrowed("scalar"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TestValue(pub f32);
+
+/// Where the rect of the last [`TestValue`] widget drawn is kept, in the temp
+/// data of the context.
+pub const VALUE_WIDGET_RECT: &str = "test_value_widget_rect";
+
+impl WidgetValueTrait for TestValue {
+    type Response = TestResponse;
+    type UserState = TestState;
+    type NodeData = TestNodeData;
+
+    fn value_widget(
+        &mut self,
+        param_name: &str,
+        _node_id: NodeId,
+        ui: &mut Ui,
+        _user_state: &mut TestState,
+        _node_data: &TestNodeData,
+    ) -> Vec<TestResponse> {
+        ui.label(param_name);
+        let response = ui.add(DragValue::new(&mut self.0));
+        // Lets the tests find the last value widget drawn
+        ui.ctx()
+            .data_mut(|data| data.insert_temp(Id::new(VALUE_WIDGET_RECT), response.rect));
+        Vec::new()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestResponse;
+
+impl UserResponseTrait for TestResponse {}
+
+#[derive(Clone, Debug, Default)]
+pub struct TestState;
+
+impl UserStateTrait for TestState {}
+
+#[derive(Clone, Debug, Default)]
+pub struct TestNodeData;
+
+impl NodeDataTrait for TestNodeData {
+    type Response = TestResponse;
+    type UserState = TestState;
+    type DataType = TestType;
+    type ValueType = TestValue;
+
+    fn bottom_ui(
+        &self,
+        _ui: &mut Ui,
+        _node_id: NodeId,
+        _graph: &TestGraph,
+        _user_state: &mut TestState,
+    ) -> Vec<NodeResponse<TestResponse, TestNodeData>> {
+        Vec::new()
+    }
+
+    fn size_limits(
+        &self,
+        _node_id: NodeId,
+        _graph: &TestGraph,
+        _user_state: &mut TestState,
+    ) -> (Vec2, Vec2) {
+        TEST_SIZE_LIMITS
+    }
+}
+
+/// The sizes the test nodes can be resized to, in graph space.
+pub const TEST_SIZE_LIMITS: (Vec2, Vec2) = (vec2(60.0, 40.0), vec2(200.0, 150.0));
+
+/// A template with a scalar input "a" and a scalar output "out".
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestTemplate {
+    pub label: &'static str,
+    pub category: &'static str,
+    pub keywords: &'static [&'static str],
+}
+
+impl TestTemplate {
+    pub const fn new(label: &'static str, category: &'static str) -> Self {
+        TestTemplate {
+            label,
+            category,
+            keywords: &[],
+        }
+    }
+
+    pub const fn with_keywords(self, keywords: &'static [&'static str]) -> Self {
+        TestTemplate { keywords, ..self }
+    }
+}
+
+impl NodeTemplateTrait for TestTemplate {
+    type NodeData = TestNodeData;
+    type DataType = TestType;
+    type ValueType = TestValue;
+    type UserState = TestState;
+    type CategoryType = &'static str;
+
+    fn node_finder_label(&self, _user_state: &mut TestState) -> Cow<'_, str> {
+        Cow::Borrowed(self.label)
+    }
+
+    fn node_finder_categories(&self, _user_state: &mut TestState) -> Vec<&'static str> {
+        vec![self.category]
+    }
+
+    fn keywords(&self, _user_state: &mut TestState) -> Vec<String> {
+        self.keywords
+            .iter()
+            .map(|keyword| keyword.to_string())
+            .collect()
+    }
+
+    fn node_graph_label(&self, _user_state: &mut TestState) -> String {
+        self.label.to_owned()
+    }
+
+    fn user_data(&self, _user_state: &mut TestState) -> TestNodeData {
+        TestNodeData
+    }
+
+    fn build_node(&self, graph: &mut TestGraph, _user_state: &mut TestState, node_id: NodeId) {
+        graph.add_input_param(
+            node_id,
+            "a".to_owned(),
+            TestType::Scalar,
+            TestValue(0.0),
+            InputParamKind::ConnectionOrConstant,
+            true,
+        );
+        graph.add_output_param(node_id, "out".to_owned(), TestType::Scalar);
+    }
+}
+
+pub type TestGraph = Graph<TestNodeData, TestType, TestValue, TestState>;
+pub type TestEditor = GraphEditorState<TestNodeData, TestType, TestValue, TestTemplate, TestState>;
+
+/// Adds a node built from `template` at `position`, in graph space.
+pub fn add_test_node(editor: &mut TestEditor, template: &TestTemplate, position: Pos2) -> NodeId {
+    let node_id = editor
+        .graph
+        .add_node(template.label.to_owned(), TestNodeData);
+    template.build_node(&mut editor.graph, &mut TestState, node_id);
+    editor.node_positions.insert(node_id, position);
+    editor.node_order.push(node_id);
+    node_id
+}
+
+/// A user command that clears the selection.
+#[derive(Clone, Debug)]
+pub struct TestCommand(pub &'static str);
+
+impl EditorCommandTrait for TestCommand {
+    type NodeData = TestNodeData;
+
+    fn label(&self, _user_state: &mut TestState) -> Cow<'_, str> {
+        Cow::Borrowed(self.0)
+    }
+
+    fn run(
+        &self,
+        _graph: &mut TestGraph,
+        selected_nodes: &mut HashSet<NodeId>,
+        _user_state: &mut TestState,
+    ) -> Vec<NodeResponse<TestResponse, TestNodeData>> {
+        selected_nodes.clear();
+        vec![NodeResponse::User(TestResponse)]
+    }
+}
+
+/// Runs a frame of `ctx` with the given input `events`, calling `add_contents`
+/// inside a central panel.
+pub fn run_frame(ctx: &Context, events: Vec<Event>, mut add_contents: impl FnMut(&mut Ui)) {
+    let input = RawInput {
+        screen_rect: Some(Rect::from_min_size(Pos2::ZERO, vec2(1024.0, 768.0))),
+        events,
+        ..Default::default()
+    };
+    let _ = ctx.run(input, |ctx| {
+        CentralPanel::default().show(ctx, |ui| add_contents(ui));
+    });
+}
+
+/// The event of pressing or releasing the primary button at `pos`.
+pub fn primary_button(pos: Pos2, pressed: bool) -> Event {
+    Event::PointerButton {
+        pos,
+        button: PointerButton::Primary,
+        pressed,
+        modifiers: Modifiers::NONE,
+    }
+}
@@ -0,0 +1,155 @@
+use egui::{Color32, Visuals};
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// The colors and sizes used to draw the graph editor. Set it through
+/// `GraphEditorState::theme`, or leave that empty to pick [`GraphTheme::dark`]
+/// or [`GraphTheme::light`] following egui's [`Visuals`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct GraphTheme {
+    /// Background of the node bodies.
+    pub node_background: Color32,
+    /// Background of the node titlebars, unless the node overrides it with
+    /// [`crate::NodeDataTrait::titlebar_color`].
+    pub node_titlebar: Color32,
+    /// Text and non-interactive strokes inside the nodes.
+    pub node_text: Color32,
+    /// Outline drawn around the selected nodes.
+    pub selection_outline: Color32,
+    /// Fill of the box selection rectangle.
+    pub box_selection_fill: Color32,
+    /// Stroke of the box selection rectangle.
+    pub box_selection_stroke: Color32,
+    /// Color of the ports close enough to the cursor to be connected.
+    pub port_hover: Color32,
+    /// Cross of the node close button.
+    pub close_button: Color32,
+    /// Cross of the node close button, when hovered.
+    pub close_button_hovered: Color32,
+    /// Cross of the node close button, when clicked.
+    pub close_button_pressed: Color32,
+    /// Lines of the handle at the bottom right corner of the nodes, dragged
+    /// to resize them.
+    pub resize_handle: Color32,
+    /// Lines of the resize handle, when hovered.
+    pub resize_handle_hovered: Color32,
+    /// Lines of the resize handle, while dragged.
+    pub resize_handle_pressed: Color32,
+    /// Background of the node finder.
+    pub finder_background: Color32,
+    /// Text in the node finder.
+    pub finder_text: Color32,
+    /// The characters of the node finder results matching the search query.
+    pub finder_highlight: Color32,
+    /// Halo drawn under the connection under the cursor.
+    pub wire_hovered: Color32,
+    /// Halo drawn under the selected connections.
+    pub wire_selected: Color32,
+    /// The line drawn by the knife gesture that cuts connections.
+    pub knife: Color32,
+    /// Width of the connections, in graph space.
+    pub wire_width: f32,
+}
+
+impl GraphTheme {
+    /// The preset for dark [`Visuals`].
+    pub const DARK: Self = Self {
+        node_background: Color32::from_rgb(0x3f, 0x3f, 0x3f),
+        node_titlebar: Color32::from_rgb(0x32, 0x32, 0x32),
+        node_text: Color32::from_rgb(0xfe, 0xfe, 0xfe),
+        selection_outline: Color32::from_rgb(0xcc, 0xcc, 0xcc),
+        box_selection_fill: Color32::from_rgba_premultiplied(60, 60, 60, 20),
+        box_selection_stroke: Color32::from_rgba_premultiplied(171, 171, 171, 180),
+        port_hover: Color32::WHITE,
+        close_button: Color32::from_rgb(0xaa, 0xaa, 0xaa),
+        close_button_hovered: Color32::from_rgb(0xdd, 0xdd, 0xdd),
+        close_button_pressed: Color32::from_rgb(0xff, 0xff, 0xff),
+        resize_handle: Color32::from_rgb(0x80, 0x80, 0x80),
+        resize_handle_hovered: Color32::from_rgb(0xdd, 0xdd, 0xdd),
+        resize_handle_pressed: Color32::from_rgb(0xff, 0xff, 0xff),
+        finder_background: Color32::from_rgb(0x3f, 0x3f, 0x3f),
+        finder_text: Color32::from_rgb(0xfe, 0xfe, 0xfe),
+        finder_highlight: Color32::from_rgb(0xff, 0xc8, 0x5a),
+        wire_hovered: Color32::from_rgba_premultiplied(122, 122, 122, 50),
+        wire_selected: Color32::from_rgba_premultiplied(208, 208, 208, 160),
+        knife: Color32::from_rgb(230, 90, 90),
+        wire_width: 5.0,
+    };
+
+    /// The preset for light [`Visuals`]. The selection outline and hovered
+    /// ports are darker than the white nodes, so they stay visible.
+    pub const LIGHT: Self = Self {
+        node_background: Color32::from_rgb(0xff, 0xff, 0xff),
+        node_titlebar: Color32::from_rgb(0xcc, 0xcc, 0xcc),
+        node_text: Color32::from_rgb(0x50, 0x50, 0x50),
+        selection_outline: Color32::from_rgb(0x3a, 0x7b, 0xd5),
+        box_selection_fill: Color32::from_rgba_premultiplied(60, 60, 60, 20),
+        box_selection_stroke: Color32::from_rgba_premultiplied(171, 171, 171, 180),
+        port_hover: Color32::from_rgb(0x22, 0x22, 0x22),
+        close_button: Color32::from_rgb(0x55, 0x55, 0x55),
+        close_button_hovered: Color32::from_rgb(0x22, 0x22, 0x22),
+        close_button_pressed: Color32::from_rgb(0x00, 0x00, 0x00),
+        resize_handle: Color32::from_rgb(0x99, 0x99, 0x99),
+        resize_handle_hovered: Color32::from_rgb(0x44, 0x44, 0x44),
+        resize_handle_pressed: Color32::from_rgb(0x00, 0x00, 0x00),
+        finder_background: Color32::from_rgb(0xfe, 0xfe, 0xfe),
+        finder_text: Color32::from_rgb(0x3f, 0x3f, 0x3f),
+        finder_highlight: Color32::from_rgb(0xc0, 0x6a, 0x00),
+        wire_hovered: Color32::from_rgba_premultiplied(0, 0, 0, 40),
+        wire_selected: Color32::from_rgba_premultiplied(0, 0, 0, 120),
+        knife: Color32::from_rgb(200, 40, 40),
+        wire_width: 5.0,
+    };
+
+    pub fn dark() -> Self {
+        Self::DARK
+    }
+
+    pub fn light() -> Self {
+        Self::LIGHT
+    }
+
+    /// The dark or light preset, depending on [`Visuals::dark_mode`].
+    pub fn from_visuals(visuals: &Visuals) -> Self {
+        if visuals.dark_mode {
+            Self::dark()
+        } else {
+            Self::light()
+        }
+    }
+}
+
+impl Default for GraphTheme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_match_unmultiplied_colors() {
+        for theme in [GraphTheme::DARK, GraphTheme::LIGHT] {
+            assert_eq!(
+                theme.box_selection_fill,
+                Color32::from_rgba_unmultiplied(200, 200, 200, 20)
+            );
+            assert_eq!(
+                theme.box_selection_stroke,
+                Color32::from_rgba_unmultiplied(200, 200, 200, 180)
+            );
+        }
+        assert_eq!(
+            GraphTheme::DARK.wire_hovered,
+            Color32::from_rgba_unmultiplied(255, 255, 255, 50)
+        );
+        assert_eq!(
+            GraphTheme::DARK.wire_selected,
+            Color32::from_rgba_unmultiplied(255, 255, 255, 160)
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use egui;
 
 use super::*;
@@ -86,7 +88,21 @@ pub trait DataTypeTrait<UserState>: PartialEq + Eq + Clone {
     ///     }
     /// }
     /// ```
-    fn name(&self) -> std::borrow::Cow<str>;
+    fn name(&self) -> std::borrow::Cow<'_, str>;
+
+    /// The width of the wires carrying this datatype, in graph space. Returns
+    /// `None` by default, which uses [`GraphTheme::wire_width`].
+    fn wire_width(&self, _user_state: &mut UserState) -> Option<f32> {
+        None
+    }
+
+    /// The length of the dashes and of the gaps between them for the wires
+    /// carrying this datatype, in graph space. Useful to tell apart optional or
+    /// event types. Returns `None` by default, which draws solid wires. Both
+    /// lengths must be finite and positive, otherwise the wires are solid.
+    fn wire_dash_pattern(&self, _user_state: &mut UserState) -> Option<(f32, f32)> {
+        None
+    }
 }
 
 /// This trait must be implemented for the `NodeData` generic parameter of the
@@ -189,6 +205,50 @@ where
     ) -> bool {
         true
     }
+
+    /// The smallest and largest sizes the node can be resized to, in graph
+    /// space, margins included. The largest size should be at least the
+    /// smallest on both axes. Where it isn't, the smallest size is used.
+    ///
+    /// Defaults to at least 60×40, and no maximum.
+    fn size_limits(
+        &self,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType, Self::UserState>,
+        _user_state: &mut Self::UserState,
+    ) -> (egui::Vec2, egui::Vec2) {
+        (egui::vec2(60.0, 40.0), egui::Vec2::INFINITY)
+    }
+
+    /// Where the ports of this node are, overriding
+    /// [`GraphEditorState::node_orientation`].
+    ///
+    /// Default implementation doesn't override it.
+    fn orientation(
+        &self,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType, Self::UserState>,
+        _user_state: &mut Self::UserState,
+    ) -> Option<NodeOrientation> {
+        None
+    }
+
+    /// Additional items of the menu opened by right-clicking the node, after
+    /// the built-in ones. The menu closes when this returns any response.
+    ///
+    /// Default implementation adds nothing.
+    fn context_menu_ui(
+        &self,
+        _ui: &mut egui::Ui,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType, Self::UserState>,
+        _user_state: &mut Self::UserState,
+    ) -> Vec<NodeResponse<Self::Response, Self>>
+    where
+        Self::Response: UserResponseTrait,
+    {
+        Default::default()
+    }
 }
 
 /// This trait can be implemented by any user type. The trait tells the library
@@ -199,6 +259,122 @@ pub trait NodeTemplateIter {
     fn all_kinds(&self) -> Vec<Self::Item>;
 }
 
+impl<T: Clone> NodeTemplateIter for Vec<T> {
+    type Item = T;
+    fn all_kinds(&self) -> Vec<T> {
+        self.clone()
+    }
+}
+
+/// An action of the editor, listed by the node finder when its query starts
+/// with `>`. The [`BuiltinCommand`]s are listed first, followed by the ones
+/// given to [`GraphEditorState::draw_graph_editor_with_commands`].
+pub trait EditorCommandTrait: Clone {
+    type NodeData: NodeDataTrait;
+
+    /// The name of the command, which the node finder query is matched
+    /// against.
+    fn label(
+        &self,
+        user_state: &mut <Self::NodeData as NodeDataTrait>::UserState,
+    ) -> std::borrow::Cow<'_, str>;
+
+    /// Identifies the command while the node finder is open, so the picked
+    /// command still runs when the list of commands changed in the meantime.
+    /// It must be unique among the commands.
+    ///
+    /// Defaults to [`EditorCommandTrait::label`].
+    fn command_id(
+        &self,
+        user_state: &mut <Self::NodeData as NodeDataTrait>::UserState,
+    ) -> std::borrow::Cow<'_, str> {
+        self.label(user_state)
+    }
+
+    /// Shown when hovering the command in the node finder.
+    fn description(
+        &self,
+        _user_state: &mut <Self::NodeData as NodeDataTrait>::UserState,
+    ) -> Option<String> {
+        None
+    }
+
+    /// Runs the command. The returned responses are handled like the ones of
+    /// the nodes, and reported back in the [`GraphResponse`].
+    #[allow(clippy::type_complexity)]
+    fn run(
+        &self,
+        graph: &mut Graph<
+            Self::NodeData,
+            <Self::NodeData as NodeDataTrait>::DataType,
+            <Self::NodeData as NodeDataTrait>::ValueType,
+            <Self::NodeData as NodeDataTrait>::UserState,
+        >,
+        selected_nodes: &mut HashSet<NodeId>,
+        user_state: &mut <Self::NodeData as NodeDataTrait>::UserState,
+    ) -> Vec<NodeResponse<<Self::NodeData as NodeDataTrait>::Response, Self::NodeData>>
+    where
+        <Self::NodeData as NodeDataTrait>::Response: UserResponseTrait;
+}
+
+/// This trait is used to list the user commands of the node finder, see
+/// [`EditorCommandTrait`].
+pub trait EditorCommandIter {
+    type Item;
+    fn all_commands(&self) -> Vec<Self::Item>;
+}
+
+impl<T: Clone> EditorCommandIter for Vec<T> {
+    type Item = T;
+    fn all_commands(&self) -> Vec<T> {
+        self.clone()
+    }
+}
+
+/// The commands of an editor with only the built-in commands. It has no
+/// values, so its methods can't be called.
+pub enum NoCommands<NodeData> {
+    #[doc(hidden)]
+    _Never(
+        std::convert::Infallible,
+        std::marker::PhantomData<fn() -> NodeData>,
+    ),
+}
+
+impl<NodeData> NoCommands<NodeData> {
+    fn never(&self) -> ! {
+        match self {
+            NoCommands::_Never(never, _) => match *never {},
+        }
+    }
+}
+
+impl<NodeData> Clone for NoCommands<NodeData> {
+    fn clone(&self) -> Self {
+        self.never()
+    }
+}
+
+impl<NodeData: NodeDataTrait> EditorCommandTrait for NoCommands<NodeData> {
+    type NodeData = NodeData;
+
+    fn label(&self, _user_state: &mut NodeData::UserState) -> std::borrow::Cow<'_, str> {
+        self.never()
+    }
+
+    fn run(
+        &self,
+        _graph: &mut Graph<NodeData, NodeData::DataType, NodeData::ValueType, NodeData::UserState>,
+        _selected_nodes: &mut HashSet<NodeId>,
+        _user_state: &mut NodeData::UserState,
+    ) -> Vec<NodeResponse<NodeData::Response, NodeData>>
+    where
+        NodeData::Response: UserResponseTrait,
+    {
+        self.never()
+    }
+}
+
 /// Describes a category of nodes.
 ///
 /// Used by [`NodeTemplateTrait::node_finder_categories`] to categorize nodes
@@ -211,6 +387,19 @@ pub trait NodeTemplateIter {
 pub trait CategoryTrait {
     /// Name of the category.
     fn name(&self) -> String;
+
+    /// Where the category sits in the tree of categories shown by the node
+    /// finder, from the outermost category in. By default, the name is split on
+    /// `/`, so a category named `"Math/Vector/Products"` is nested in
+    /// `"Vector"`, itself nested in `"Math"`.
+    fn path(&self) -> Vec<String> {
+        self.name()
+            .split('/')
+            .map(str::trim)
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_owned)
+            .collect()
+    }
 }
 
 impl CategoryTrait for () {
@@ -219,7 +408,7 @@ impl CategoryTrait for () {
     }
 }
 
-impl<'a> CategoryTrait for &'a str {
+impl CategoryTrait for &str {
     fn name(&self) -> String {
         self.to_string()
     }
@@ -256,7 +445,17 @@ pub trait NodeTemplateTrait: Clone {
     /// The return type is Cow<str> to allow returning owned or borrowed values
     /// more flexibly. Refer to the documentation for `DataTypeTrait::name` for
     /// more information
-    fn node_finder_label(&self, user_state: &mut Self::UserState) -> std::borrow::Cow<str>;
+    fn node_finder_label(&self, user_state: &mut Self::UserState) -> std::borrow::Cow<'_, str>;
+
+    /// Identifies the template in the favourite and recently used templates
+    /// of the node finder, see [`NodeFinderMemory`]. It must be unique among
+    /// the templates and stay the same from one run to the next, even if the
+    /// labels are translated.
+    ///
+    /// Defaults to [`NodeTemplateTrait::node_finder_label`].
+    fn node_template_id(&self, user_state: &mut Self::UserState) -> std::borrow::Cow<'_, str> {
+        self.node_finder_label(user_state)
+    }
 
     /// Vec of categories to which the node belongs.
     ///
@@ -267,6 +466,26 @@ pub trait NodeTemplateTrait: Clone {
         Vec::default()
     }
 
+    /// Other names the node finder matches the search query against, in
+    /// addition to [`NodeTemplateTrait::node_finder_label`]. For example, a
+    /// "Multiply" node could be found by typing "times" or "product".
+    fn node_finder_aliases(&self, _user_state: &mut Self::UserState) -> Vec<String> {
+        Vec::default()
+    }
+
+    /// Words the node finder matches the search query against, like
+    /// [`NodeTemplateTrait::node_finder_aliases`] but ranked a bit lower. For
+    /// example, "interpolate" or "blend" for a "Lerp" node.
+    fn keywords(&self, _user_state: &mut Self::UserState) -> Vec<String> {
+        Vec::default()
+    }
+
+    /// What nodes built from this template do. Shown in the node finder when
+    /// hovering the template, along with its ports.
+    fn description(&self, _user_state: &mut Self::UserState) -> Option<String> {
+        None
+    }
+
     /// Returns a descriptive name for the node kind, used in the graph.
     fn node_graph_label(&self, user_state: &mut Self::UserState) -> String;
 
@@ -282,6 +501,22 @@ pub trait NodeTemplateTrait: Clone {
         user_state: &mut Self::UserState,
         node_id: NodeId,
     );
+
+    /// The ports of the nodes built from this template. Used by the node
+    /// finder to only offer templates that can connect to a dragged port.
+    ///
+    /// The default implementation calls [`NodeTemplateTrait::build_node`] on
+    /// a scratch graph, leaving the user's graph untouched. Override it when
+    /// building a node is expensive or has side effects on the `user_state`.
+    fn node_signature(&self, user_state: &mut Self::UserState) -> NodeSignature<Self::DataType> {
+        let mut graph = Graph::new();
+        let node_id = graph.add_node(
+            self.node_graph_label(user_state),
+            self.user_data(user_state),
+        );
+        self.build_node(&mut graph, user_state, node_id);
+        graph.node_signature(node_id)
+    }
 }
 
 /// The custom user response types when drawing nodes in the graph must
@@ -3,7 +3,8 @@ use std::marker::PhantomData;
 use std::sync::Arc;
 
 use egui::emath::GuiRounding;
-use egui::{Style, Ui, Vec2};
+use egui::emath::TSTransform;
+use egui::{Pos2, Rect, Style, Ui, Vec2};
 #[cfg(feature = "persistence")]
 use serde::{Deserialize, Serialize};
 
@@ -13,6 +14,9 @@ use crate::scale::Scale;
 
 const MIN_ZOOM: f32 = 0.2;
 const MAX_ZOOM: f32 = 2.0;
+const ZOOM_SPEED: f32 = 0.002;
+const AUTO_PAN_MARGIN: f32 = 40.0;
+const AUTO_PAN_SPEED: f32 = 800.0;
 
 #[derive(Clone)]
 #[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
@@ -33,14 +37,61 @@ pub struct GraphEditorState<
     /// The currently selected node. Some interface actions depend on the
     /// currently selected node.
     pub selected_nodes: HashSet<NodeId>,
-    /// The mouse drag start position for an ongoing box selection.
+    /// The currently selected connections, identified by their input.
+    pub selected_wires: HashSet<InputId>,
+    /// The connection whose context menu is open, and the screen position of
+    /// the menu.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub wire_context_menu: Option<(InputId, Pos2)>,
+    /// The node whose context menu is open, and the screen position of the
+    /// menu.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub node_context_menu: Option<(NodeId, Pos2)>,
+    /// The mouse drag start position for an ongoing box selection, in graph
+    /// space.
     pub ongoing_box_selection: Option<egui::Pos2>,
-    /// The position of each node.
+    /// The line drawn so far by an ongoing knife gesture, which cuts the
+    /// connections it crosses, in graph space.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub ongoing_knife: Option<Vec<egui::Pos2>>,
+    /// The position of each node, in graph space. See [`PanZoom`] for
+    /// conversions to and from screen space.
     pub node_positions: SecondaryMap<NodeId, egui::Pos2>,
+    /// The size of the nodes resized by the user, in graph space. The other
+    /// nodes fit their contents, up to [`GraphNodeWidget::MAX_NODE_SIZE`].
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub node_sizes: SecondaryMap<NodeId, egui::Vec2>,
+    /// The nodes showing only their titlebar.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub collapsed_nodes: HashSet<NodeId>,
+    /// The rect of each node as of the last drawn frame, in graph space. Used
+    /// by view commands like [`GraphEditorState::zoom_to_fit`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub node_rects: NodeRects,
     /// The node finder is used to create new nodes.
     pub node_finder: Option<NodeFinder<NodeTemplate>>,
+    /// The recently used and favourite templates of the node finder.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub node_finder_memory: NodeFinderMemory,
+    /// The templates listed by [`GraphEditorState::draw_node_palette`].
+    #[cfg_attr(feature = "persistence", serde(skip, default = "NodePalette::default"))]
+    pub node_palette: NodePalette<NodeTemplate>,
     /// The panning of the graph viewport.
     pub pan_zoom: PanZoom,
+    /// The colors used to draw the editor. When `None`, the dark or light
+    /// preset is picked following egui's `Visuals`.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub theme: Option<GraphTheme>,
+    /// The shape of the connections. See [`WireStyle`].
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub wire_style: WireStyle,
+    /// Where the ports of the nodes are, unless
+    /// [`NodeDataTrait::orientation`] says otherwise.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub node_orientation: NodeOrientation,
+    /// The cached routes of the connections, for [`WireStyle::Routed`].
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub wire_routes: WireRouteCache,
     pub _user_state: PhantomData<fn() -> UserState>,
 }
 
@@ -68,13 +119,26 @@ impl<
         editor_state.node_finder = None;
         editor_state.connection_in_progress = None;
         editor_state.ongoing_box_selection = None;
+        editor_state.ongoing_knife = None;
         editor_state.selected_nodes.clear();
+        editor_state.selected_wires.clear();
+        editor_state.wire_context_menu = None;
+        editor_state.node_context_menu = None;
         editor_state
             .node_order
             .retain(|id| editor_state.graph.nodes.contains_key(*id));
         editor_state
             .node_positions
             .retain(|id, _pos| editor_state.graph.nodes.contains_key(id));
+        editor_state
+            .node_rects
+            .retain(|id, _rect| editor_state.graph.nodes.contains_key(*id));
+        editor_state
+            .node_sizes
+            .retain(|id, _size| editor_state.graph.nodes.contains_key(id));
+        editor_state
+            .collapsed_nodes
+            .retain(|id| editor_state.graph.nodes.contains_key(*id));
 
         editor_state
     }
@@ -94,25 +158,104 @@ impl<
             node_order: Default::default(),
             connection_in_progress: Default::default(),
             selected_nodes: Default::default(),
+            selected_wires: Default::default(),
+            wire_context_menu: Default::default(),
+            node_context_menu: Default::default(),
             ongoing_box_selection: Default::default(),
+            ongoing_knife: Default::default(),
             node_positions: Default::default(),
+            node_sizes: Default::default(),
+            collapsed_nodes: Default::default(),
+            node_rects: Default::default(),
             node_finder: Default::default(),
+            node_finder_memory: Default::default(),
+            node_palette: Default::default(),
             pan_zoom: Default::default(),
+            theme: Default::default(),
+            wire_style: Default::default(),
+            node_orientation: Default::default(),
+            wire_routes: Default::default(),
             _user_state: Default::default(),
         }
     }
 }
 
+/// The camera of the graph editor.
+///
+/// Node positions are stored in graph space, which doesn't depend on the view.
+/// The `pan` and `zoom` describe how graph space maps onto the screen:
+/// `screen = editor_rect.min + pan + graph * zoom`.
 #[derive(Clone)]
 #[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "persistence", serde(default))]
 pub struct PanZoom {
+    /// Screen space offset of the graph origin from the top left corner of
+    /// the editor rect.
     pub pan: Vec2,
+    /// Screen space size of one unit in graph space.
     pub zoom: f32,
+    /// The style used to draw the nodes with [`ZoomRenderMode::Restyle`].
     #[cfg_attr(feature = "persistence", serde(skip, default))]
     pub zoomed_style: Arc<Style>,
     #[cfg_attr(feature = "persistence", serde(skip, default))]
     pub started: bool,
     pub enable_zoom_from_out_of_rect: bool,
+    /// The smallest zoom level the user can reach.
+    pub min_zoom: f32,
+    /// The largest zoom level the user can reach.
+    pub max_zoom: f32,
+    /// How much a scroll of one point changes the zoom level, when scrolling
+    /// zooms. The zoom is multiplied by `(scroll_delta * zoom_speed).exp()`.
+    pub zoom_speed: f32,
+    /// What scrolling over the editor does. See [`ScrollBehavior`].
+    pub scroll_behavior: ScrollBehavior,
+    /// Distance, in screen space, from the editor edges at which dragging a
+    /// node, a connection or a box selection starts panning the view. Set to
+    /// zero to disable auto-panning.
+    pub auto_pan_margin: f32,
+    /// Auto-pan speed in points per second, reached when the cursor is at the
+    /// edge of the editor or beyond it.
+    pub auto_pan_speed: f32,
+    /// How the zoom is rendered. See [`ZoomRenderMode`].
+    pub render_mode: ZoomRenderMode,
+    /// The screen rect the graph was last drawn in. Updated by
+    /// `draw_graph_editor` every frame.
+    #[cfg_attr(feature = "persistence", serde(skip, default = "default_editor_rect"))]
+    pub editor_rect: Rect,
+}
+
+/// The ways the graph editor can render its zoom level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum ZoomRenderMode {
+    /// Draws the graph unscaled inside a layer, and lets egui apply the camera
+    /// transform to the whole layer. Every widget inside the nodes scales
+    /// correctly, including custom ones with fixed pixel sizes, but text is
+    /// rasterized at its unscaled size.
+    #[default]
+    LayerTransform,
+    /// Draws the graph with a copy of the egui `Style` scaled by the zoom
+    /// level, see [`PanZoom::zoomed_style`]. Text stays crisp, but style fields
+    /// that aren't scaled and widgets with fixed pixel sizes don't follow the
+    /// zoom.
+    Restyle,
+}
+
+/// What scrolling over the graph editor does. Pinch gestures and `Ctrl` +
+/// scroll, which egui reports as a zoom, always zoom.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum ScrollBehavior {
+    /// Vertical scrolling zooms the graph. Suits mouse wheels.
+    #[default]
+    Zoom,
+    /// Scrolling pans the graph in both directions. Suits trackpads, where
+    /// two-finger scrolling is expected to pan.
+    Pan,
+}
+
+fn default_editor_rect() -> Rect {
+    Rect::ZERO
 }
 
 impl Default for PanZoom {
@@ -123,6 +266,14 @@ impl Default for PanZoom {
             zoomed_style: Default::default(),
             started: false,
             enable_zoom_from_out_of_rect: false,
+            min_zoom: MIN_ZOOM,
+            max_zoom: MAX_ZOOM,
+            zoom_speed: ZOOM_SPEED,
+            scroll_behavior: Default::default(),
+            auto_pan_margin: AUTO_PAN_MARGIN,
+            auto_pan_speed: AUTO_PAN_SPEED,
+            render_mode: Default::default(),
+            editor_rect: default_editor_rect(),
         }
     }
 }
@@ -131,19 +282,44 @@ impl PanZoom {
     pub fn new(zoom: f32) -> PanZoom {
         let style: Style = Default::default();
         PanZoom {
-            pan: Vec2::ZERO,
             zoom,
             zoomed_style: Arc::new(style.scaled(1.0)),
-            started: false,
-            enable_zoom_from_out_of_rect: false,
+            ..Default::default()
         }
     }
 
     pub fn zoom(&mut self, style: &Arc<Style>, zoom_delta: f32) {
-        let new_zoom = (self.zoom * zoom_delta).clamp(MIN_ZOOM, MAX_ZOOM);
+        let new_zoom = (self.zoom * zoom_delta).clamp(self.min_zoom, self.max_zoom);
         self.zoomed_style = Arc::new(style.scaled(new_zoom));
         self.zoom = new_zoom;
     }
+
+    /// The transform from graph space to screen space.
+    pub fn graph_to_screen_transform(&self) -> TSTransform {
+        TSTransform::new(self.editor_rect.min.to_vec2() + self.pan, self.zoom)
+    }
+
+    /// Converts a position in graph space, like the ones in
+    /// `GraphEditorState::node_positions`, to screen space.
+    pub fn graph_to_screen(&self, pos: Pos2) -> Pos2 {
+        self.graph_to_screen_transform() * pos
+    }
+
+    /// Converts a position in screen space, like the pointer position, to
+    /// graph space.
+    pub fn screen_to_graph(&self, pos: Pos2) -> Pos2 {
+        self.graph_to_screen_transform().inverse() * pos
+    }
+
+    /// Converts a rect in graph space to screen space.
+    pub fn graph_to_screen_rect(&self, rect: Rect) -> Rect {
+        self.graph_to_screen_transform() * rect
+    }
+
+    /// Converts a rect in screen space to graph space.
+    pub fn screen_to_graph_rect(&self, rect: Rect) -> Rect {
+        self.graph_to_screen_transform().inverse() * rect
+    }
 }
 
 pub fn show_zoomed<R, F>(
@@ -175,3 +351,23 @@ where
 
     response
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_graph_screen_round_trip() {
+        let pan_zoom = PanZoom {
+            pan: egui::vec2(30.0, -12.0),
+            zoom: 0.5,
+            editor_rect: Rect::from_min_size(egui::pos2(100.0, 50.0), egui::vec2(800.0, 600.0)),
+            ..Default::default()
+        };
+
+        let graph_pos = egui::pos2(40.0, 80.0);
+        let screen_pos = pan_zoom.graph_to_screen(graph_pos);
+        assert_eq!(screen_pos, egui::pos2(150.0, 78.0));
+        assert_eq!(pan_zoom.screen_to_graph(screen_pos), graph_pos);
+    }
+}
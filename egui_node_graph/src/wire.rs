@@ -0,0 +1,634 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use egui::epaint::CubicBezierShape;
+use egui::{Color32, Painter, Pos2, Rect, Shape, Stroke, Vec2};
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+use crate::{InputId, NodeId, NodeRects};
+
+/// Minimum length, in graph space, of the horizontal segments leaving and
+/// entering the ports with [`WireStyle::Orthogonal`] and [`WireStyle::Routed`].
+const ORTHOGONAL_STUB: f32 = 20.0;
+/// Size, in graph space, of the grid cells used by [`WireStyle::Routed`].
+const ROUTING_GRID: f32 = 20.0;
+/// Clearance, in graph space, kept between routed wires and the nodes.
+const ROUTING_MARGIN: f32 = 10.0;
+/// Past this many grid cells, routing gives up and falls back to
+/// [`WireStyle::Orthogonal`].
+const ROUTING_MAX_CELLS: usize = 100_000;
+/// How far, in graph space, the end points and nearby nodes of a cached route
+/// can drift before it is found again.
+const ROUTE_TOLERANCE: f32 = 0.01;
+/// The cost of a bend in a routed wire, in grid steps. Keeps the routes from
+/// zigzagging.
+const ROUTING_BEND_COST: u32 = 3;
+
+/// How the ports of a node are laid out, which is also the direction the
+/// wires leave and enter them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum NodeOrientation {
+    /// Inputs on the left edge and outputs on the right edge.
+    #[default]
+    Horizontal,
+    /// Inputs on the top edge and outputs on the bottom edge.
+    Vertical,
+}
+
+impl NodeOrientation {
+    /// The direction from the inputs to the outputs of a node.
+    pub fn flow(self) -> Vec2 {
+        match self {
+            NodeOrientation::Horizontal => Vec2::X,
+            NodeOrientation::Vertical => Vec2::Y,
+        }
+    }
+}
+
+/// The shape of the connections between ports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum WireStyle {
+    /// A cubic Bézier curve leaving and entering the ports horizontally.
+    #[default]
+    Bezier,
+    /// A straight line between the two ports.
+    Straight,
+    /// Horizontal and vertical segments only, also known as Manhattan routing.
+    Orthogonal,
+    /// Like [`WireStyle::Orthogonal`], but going around the nodes in the way.
+    /// Routes are found with A* on a coarse grid and cached in
+    /// [`WireRouteCache`].
+    Routed,
+}
+
+impl WireStyle {
+    /// The wire between `src` and `dst` as a polyline, in the same space as
+    /// the given positions. `zoom` is the scale of that space relative to
+    /// graph space.
+    pub fn points(&self, src: Pos2, dst: Pos2, zoom: f32) -> Vec<Pos2> {
+        self.oriented_points(src, dst, [NodeOrientation::Horizontal; 2], zoom)
+    }
+
+    /// Like [`WireStyle::points`], for a wire leaving `src` on a node with
+    /// the first orientation and entering `dst` on a node with the second.
+    ///
+    /// Between two vertical nodes, the wires are the horizontal ones turned
+    /// on their side. Between nodes of different orientations,
+    /// [`WireStyle::Routed`] wires don't go around the nodes.
+    pub fn oriented_points(
+        &self,
+        src: Pos2,
+        dst: Pos2,
+        orientations: [NodeOrientation; 2],
+        zoom: f32,
+    ) -> Vec<Pos2> {
+        use NodeOrientation::*;
+        match orientations {
+            [Horizontal, Horizontal] => self.horizontal_points(src, dst, zoom),
+            [Vertical, Vertical] => self
+                .horizontal_points(transpose(src), transpose(dst), zoom)
+                .into_iter()
+                .map(transpose)
+                .collect(),
+            [src_orientation, dst_orientation] => {
+                let (src_flow, dst_flow) = (src_orientation.flow(), dst_orientation.flow());
+                match self {
+                    WireStyle::Bezier => {
+                        let control_scale = ((dst - src).length() * 0.333).max(30.0) * zoom;
+                        bezier_points(
+                            src,
+                            src + src_flow * control_scale,
+                            dst - dst_flow * control_scale,
+                            dst,
+                        )
+                    }
+                    WireStyle::Straight => vec![src, dst],
+                    WireStyle::Orthogonal | WireStyle::Routed => {
+                        // Keep going the way the wire leaves, then turn to the
+                        // way it enters
+                        let stub = ORTHOGONAL_STUB * zoom;
+                        let (start, end) = (src + src_flow * stub, dst - dst_flow * stub);
+                        let corner = match src_orientation {
+                            Horizontal => Pos2::new(end.x, start.y),
+                            Vertical => Pos2::new(start.x, end.y),
+                        };
+                        vec![src, start, corner, end, dst]
+                    }
+                }
+            }
+        }
+    }
+
+    /// The wire leaving `src` and entering `dst` horizontally.
+    fn horizontal_points(&self, src: Pos2, dst: Pos2, zoom: f32) -> Vec<Pos2> {
+        match self {
+            WireStyle::Bezier => {
+                let control_scale = ((dst.x - src.x) * 0.333).max(30.0) * zoom;
+                let src_control = src + Vec2::X * control_scale;
+                let dst_control = dst - Vec2::X * control_scale;
+                bezier_points(src, src_control, dst_control, dst)
+            }
+            WireStyle::Straight => vec![src, dst],
+            WireStyle::Orthogonal | WireStyle::Routed => {
+                let stub = ORTHOGONAL_STUB * zoom;
+                if dst.x - src.x >= 2.0 * stub {
+                    let mid_x = (src.x + dst.x) / 2.0;
+                    vec![src, Pos2::new(mid_x, src.y), Pos2::new(mid_x, dst.y), dst]
+                } else {
+                    // The input is behind the output: go around through the
+                    // vertical middle point.
+                    let mid_y = (src.y + dst.y) / 2.0;
+                    vec![
+                        src,
+                        Pos2::new(src.x + stub, src.y),
+                        Pos2::new(src.x + stub, mid_y),
+                        Pos2::new(dst.x - stub, mid_y),
+                        Pos2::new(dst.x - stub, dst.y),
+                        dst,
+                    ]
+                }
+            }
+        }
+    }
+}
+
+fn bezier_points(src: Pos2, src_control: Pos2, dst_control: Pos2, dst: Pos2) -> Vec<Pos2> {
+    CubicBezierShape::from_points_stroke(
+        [src, src_control, dst_control, dst],
+        false,
+        Color32::TRANSPARENT,
+        Stroke::NONE,
+    )
+    .flatten(None)
+}
+
+/// Swaps the coordinates, turning horizontal wires into vertical ones and
+/// back.
+fn transpose(pos: Pos2) -> Pos2 {
+    Pos2::new(pos.y, pos.x)
+}
+
+/// A wire route found by [`WireStyle::Routed`], in graph space.
+#[derive(Clone, Debug)]
+pub struct CachedWireRoute {
+    pub src: Pos2,
+    pub dst: Pos2,
+    pub orientations: [NodeOrientation; 2],
+    /// The nodes close to the route when it was found, including its end
+    /// nodes, with their rects. See [`nearby_nodes`].
+    pub nearby_nodes: Vec<(NodeId, Rect)>,
+    pub points: Vec<Pos2>,
+}
+
+impl CachedWireRoute {
+    /// Whether the route can still be used: its end points didn't move, and
+    /// no node moved next to it or away from it.
+    fn is_valid(
+        &self,
+        src: Pos2,
+        dst: Pos2,
+        orientations: [NodeOrientation; 2],
+        node_rects: &NodeRects,
+    ) -> bool {
+        let same_rect = |a: Rect, b: Rect| same_pos(a.min, b.min) && same_pos(a.max, b.max);
+        let nearby = nearby_nodes(&self.points, node_rects);
+        same_pos(self.src, src)
+            && same_pos(self.dst, dst)
+            && self.orientations == orientations
+            && nearby.len() == self.nearby_nodes.len()
+            && nearby.iter().zip(&self.nearby_nodes).all(
+                |((id, rect), (cached_id, cached_rect))| {
+                    id == cached_id && same_rect(*rect, *cached_rect)
+                },
+            )
+    }
+}
+
+/// Whether two positions in graph space are the same, give or take the
+/// rounding errors of converting them from screen space.
+fn same_pos(a: Pos2, b: Pos2) -> bool {
+    (a - b).length_sq() < ROUTE_TOLERANCE * ROUTE_TOLERANCE
+}
+
+/// The nodes a route depends on: the ones close enough to its bounding box
+/// that moving them could change it, sorted by id.
+fn nearby_nodes(points: &[Pos2], node_rects: &NodeRects) -> Vec<(NodeId, Rect)> {
+    let bounds = Rect::from_points(points).expand(ROUTING_MARGIN + ROUTING_GRID);
+    let mut nodes: Vec<(NodeId, Rect)> = node_rects
+        .iter()
+        .filter(|(_, rect)| rect.intersects(bounds))
+        .map(|(node_id, rect)| (*node_id, *rect))
+        .collect();
+    nodes.sort_by_key(|(node_id, _)| *node_id);
+    nodes
+}
+
+/// The routes of the connections drawn with [`WireStyle::Routed`], keyed by
+/// the input end of the connection. A route is only recomputed when one of its
+/// end points moves, or a node moves next to it or away from it.
+#[derive(Clone, Debug, Default)]
+pub struct WireRouteCache {
+    pub routes: HashMap<InputId, CachedWireRoute>,
+    /// The route of the connection being dragged, which has no input yet.
+    pub in_progress: Option<CachedWireRoute>,
+}
+
+impl WireRouteCache {
+    /// The route from `src` to `dst` avoiding `node_rects`, all in graph space.
+    /// Reuses the cached route for `input`, or for the connection being
+    /// dragged when it's `None`, unless its end points or the nodes around it
+    /// moved. See [`route_around_oriented`] for the `orientations`.
+    pub fn route(
+        &mut self,
+        input: Option<InputId>,
+        src: Pos2,
+        dst: Pos2,
+        orientations: [NodeOrientation; 2],
+        node_rects: &NodeRects,
+    ) -> Vec<Pos2> {
+        let cached = match input {
+            Some(input) => self.routes.get(&input),
+            None => self.in_progress.as_ref(),
+        };
+        if let Some(cached) = cached {
+            if cached.is_valid(src, dst, orientations, node_rects) {
+                return cached.points.clone();
+            }
+        }
+        let points = route_around_oriented(src, dst, orientations, node_rects);
+        let cached = CachedWireRoute {
+            src,
+            dst,
+            orientations,
+            nearby_nodes: nearby_nodes(&points, node_rects),
+            points: points.clone(),
+        };
+        match input {
+            Some(input) => {
+                self.routes.insert(input, cached);
+            }
+            None => self.in_progress = Some(cached),
+        }
+        points
+    }
+
+    /// Drops the routes of the connections for which `keep` returns false.
+    pub fn retain(&mut self, mut keep: impl FnMut(InputId) -> bool) {
+        self.routes.retain(|input, _| keep(*input));
+    }
+}
+
+/// Like [`route_around`], for a wire leaving `src` on a node with the first
+/// orientation and entering `dst` on a node with the second. Only wires
+/// between nodes of the same orientation are routed, see
+/// [`WireStyle::oriented_points`].
+pub fn route_around_oriented(
+    src: Pos2,
+    dst: Pos2,
+    orientations: [NodeOrientation; 2],
+    node_rects: &NodeRects,
+) -> Vec<Pos2> {
+    match orientations {
+        [NodeOrientation::Horizontal, NodeOrientation::Horizontal] => {
+            route_around(src, dst, node_rects)
+        }
+        [NodeOrientation::Vertical, NodeOrientation::Vertical] => {
+            let transposed: NodeRects = node_rects
+                .iter()
+                .map(|(node_id, rect)| {
+                    let rect = Rect::from_two_pos(transpose(rect.min), transpose(rect.max));
+                    (*node_id, rect)
+                })
+                .collect();
+            route_around(transpose(src), transpose(dst), &transposed)
+                .into_iter()
+                .map(transpose)
+                .collect()
+        }
+        _ => WireStyle::Routed.oriented_points(src, dst, orientations, 1.0),
+    }
+}
+
+/// Finds an orthogonal path from `src` to `dst`, in graph space, that keeps
+/// clear of `node_rects`. Falls back to the [`WireStyle::Orthogonal`] shape
+/// when there is no path or the search area is too large.
+pub fn route_around(src: Pos2, dst: Pos2, node_rects: &NodeRects) -> Vec<Pos2> {
+    let start = src + Vec2::X * ORTHOGONAL_STUB;
+    let goal = dst - Vec2::X * ORTHOGONAL_STUB;
+    let obstacles: Vec<Rect> = node_rects
+        .values()
+        .map(|rect| rect.expand(ROUTING_MARGIN))
+        .collect();
+
+    // The grid is aligned on the start point, and covers the end points and
+    // every node between them, with some room to go around.
+    let mut area = Rect::from_two_pos(start, goal).expand(4.0 * ROUTING_GRID);
+    for obstacle in &obstacles {
+        if obstacle.intersects(area) {
+            area = area.union(*obstacle);
+        }
+    }
+    let area = area.expand(2.0 * ROUTING_GRID);
+    let min_i = ((area.min.x - start.x) / ROUTING_GRID).floor() as i32;
+    let min_j = ((area.min.y - start.y) / ROUTING_GRID).floor() as i32;
+    let width = ((area.max.x - start.x) / ROUTING_GRID).ceil() as i32 - min_i + 1;
+    let height = ((area.max.y - start.y) / ROUTING_GRID).ceil() as i32 - min_j + 1;
+    if (width as usize) * (height as usize) > ROUTING_MAX_CELLS {
+        return WireStyle::Orthogonal.points(src, dst, 1.0);
+    }
+
+    let cell_pos = |cell: usize| {
+        let i = (cell % width as usize) as i32 + min_i;
+        let j = (cell / width as usize) as i32 + min_j;
+        start + Vec2::new(i as f32, j as f32) * ROUTING_GRID
+    };
+    let cell_at = |pos: Pos2| {
+        let i = ((pos.x - start.x) / ROUTING_GRID).round() as i32 - min_i;
+        let j = ((pos.y - start.y) / ROUTING_GRID).round() as i32 - min_j;
+        (j * width + i) as usize
+    };
+    let start_cell = cell_at(start);
+    let goal_cell = cell_at(goal);
+    let blocked: Vec<bool> = (0..(width * height) as usize)
+        .map(|cell| {
+            cell != start_cell
+                && cell != goal_cell
+                && obstacles.iter().any(|rect| rect.contains(cell_pos(cell)))
+        })
+        .collect();
+
+    // A* over (cell, direction) states, so bends can be given a cost. The
+    // directions are right, down, left and up, and wires leave to the right.
+    const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let goal_pos = cell_pos(goal_cell);
+    let heuristic = |cell: usize| {
+        let pos = cell_pos(cell);
+        (((pos.x - goal_pos.x).abs() + (pos.y - goal_pos.y).abs()) / ROUTING_GRID).round() as u32
+    };
+    let mut costs = vec![u32::MAX; blocked.len() * 4];
+    let mut came_from = vec![usize::MAX; blocked.len() * 4];
+    let mut open = BinaryHeap::new();
+    costs[start_cell * 4] = 0;
+    open.push(Reverse((heuristic(start_cell), 0, start_cell * 4)));
+
+    let mut reached = None;
+    while let Some(Reverse((_, cost, state))) = open.pop() {
+        if cost > costs[state] {
+            continue;
+        }
+        let (cell, direction) = (state / 4, state % 4);
+        if cell == goal_cell {
+            reached = Some(state);
+            break;
+        }
+        let i = (cell % width as usize) as i32;
+        let j = (cell / width as usize) as i32;
+        for (next_direction, (di, dj)) in DIRECTIONS.iter().enumerate() {
+            if next_direction == (direction + 2) % 4 {
+                continue;
+            }
+            let (ni, nj) = (i + di, j + dj);
+            if ni < 0 || nj < 0 || ni >= width || nj >= height {
+                continue;
+            }
+            let next_cell = (nj * width + ni) as usize;
+            if blocked[next_cell] {
+                continue;
+            }
+            let bend = if next_direction == direction {
+                0
+            } else {
+                ROUTING_BEND_COST
+            };
+            let next_cost = cost + 1 + bend;
+            let next_state = next_cell * 4 + next_direction;
+            if next_cost < costs[next_state] {
+                costs[next_state] = next_cost;
+                came_from[next_state] = state;
+                open.push(Reverse((
+                    next_cost + heuristic(next_cell),
+                    next_cost,
+                    next_state,
+                )));
+            }
+        }
+    }
+
+    let Some(mut state) = reached else {
+        return WireStyle::Orthogonal.points(src, dst, 1.0);
+    };
+    let mut cells = vec![state / 4];
+    while came_from[state] != usize::MAX {
+        state = came_from[state];
+        cells.push(state / 4);
+    }
+    cells.reverse();
+
+    // Only keep the corners of the path. The goal cell may be off the
+    // destination row, in which case the wire enters it vertically first.
+    let mut points = vec![src];
+    points.extend(cells.into_iter().map(cell_pos));
+    points.push(Pos2::new(goal_pos.x, dst.y));
+    points.push(dst);
+    points.dedup();
+    let mut corners = vec![points[0]];
+    for window in points.windows(3) {
+        let (a, b, c) = (window[0], window[1], window[2]);
+        let straight = (a.x == b.x && b.x == c.x) || (a.y == b.y && b.y == c.y);
+        if !straight {
+            corners.push(b);
+        }
+    }
+    corners.push(*points.last().unwrap());
+    corners
+}
+
+/// The distance from `pos` to the closest point of the polyline `points`.
+pub fn distance_to_polyline(points: &[Pos2], pos: Pos2) -> f32 {
+    if let [point] = points {
+        return point.distance(pos);
+    }
+    points
+        .windows(2)
+        .map(|segment| {
+            let (a, b) = (segment[0], segment[1]);
+            let ab = b - a;
+            let t = if ab.length_sq() > 0.0 {
+                ((pos - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            (a + ab * t).distance(pos)
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Whether the polylines `a` and `b` cross each other.
+pub fn polylines_intersect(a: &[Pos2], b: &[Pos2]) -> bool {
+    fn cross(o: Pos2, a: Pos2, b: Pos2) -> f32 {
+        (a - o).x * (b - o).y - (a - o).y * (b - o).x
+    }
+    fn segments_intersect(p1: Pos2, p2: Pos2, q1: Pos2, q2: Pos2) -> bool {
+        let d1 = cross(q1, q2, p1);
+        let d2 = cross(q1, q2, p2);
+        let d3 = cross(p1, p2, q1);
+        let d4 = cross(p1, p2, q2);
+        d1 * d2 < 0.0 && d3 * d4 < 0.0
+    }
+    a.windows(2).any(|sa| {
+        b.windows(2)
+            .any(|sb| segments_intersect(sa[0], sa[1], sb[0], sb[1]))
+    })
+}
+
+/// Whether the polyline `points` goes through `rect`.
+pub fn polyline_intersects_rect(points: &[Pos2], rect: Rect) -> bool {
+    let outline = [
+        rect.left_top(),
+        rect.right_top(),
+        rect.right_bottom(),
+        rect.left_bottom(),
+        rect.left_top(),
+    ];
+    points.iter().any(|point| rect.contains(*point)) || polylines_intersect(points, &outline)
+}
+
+/// Draws a wire along `points`. `dash_pattern` is the length of the dashes
+/// and of the gaps between them, in the same space as the points. A solid line
+/// is drawn when it's `None`, or when a length isn't finite and positive.
+pub fn draw_wire(
+    painter: &Painter,
+    points: Vec<Pos2>,
+    stroke: Stroke,
+    dash_pattern: Option<(f32, f32)>,
+) {
+    match dash_pattern.filter(|pattern| is_valid_dash_pattern(*pattern)) {
+        Some((dash_length, gap_length)) => {
+            painter.extend(Shape::dashed_line(&points, stroke, dash_length, gap_length));
+        }
+        None => {
+            painter.add(Shape::line(points, stroke));
+        }
+    }
+}
+
+/// Whether `Shape::dashed_line` can draw the pattern. Zero lengths would make
+/// it loop forever.
+fn is_valid_dash_pattern((dash_length, gap_length): (f32, f32)) -> bool {
+    [dash_length, gap_length]
+        .iter()
+        .all(|length| length.is_finite() && *length > 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeId;
+    use slotmap::SlotMap;
+
+    #[test]
+    pub fn test_route_around_node() {
+        let mut nodes = SlotMap::<NodeId, ()>::with_key();
+        let mut node_rects = NodeRects::new();
+        node_rects.insert(
+            nodes.insert(()),
+            Rect::from_min_max(Pos2::new(150.0, -50.0), Pos2::new(250.0, 50.0)),
+        );
+
+        let src = Pos2::new(0.0, 0.0);
+        let dst = Pos2::new(400.0, 0.0);
+        let route = route_around(src, dst, &node_rects);
+
+        assert_eq!(route.first(), Some(&src));
+        assert_eq!(route.last(), Some(&dst));
+        for segment in route.windows(2) {
+            assert!(segment[0].x == segment[1].x || segment[0].y == segment[1].y);
+            let bounds = Rect::from_two_pos(segment[0], segment[1]);
+            assert!(!node_rects.values().any(|rect| rect.intersects(bounds)));
+        }
+    }
+
+    #[test]
+    pub fn test_vertical_wires_leave_downwards() {
+        let src = Pos2::new(0.0, 0.0);
+        let dst = Pos2::new(100.0, 200.0);
+        for style in [WireStyle::Bezier, WireStyle::Orthogonal] {
+            let points = style.oriented_points(src, dst, [NodeOrientation::Vertical; 2], 1.0);
+            assert_eq!(points.first(), Some(&src));
+            assert_eq!(points.last(), Some(&dst));
+            assert!(points[1].y > src.y && (points[1].x - src.x).abs() < 1.0);
+        }
+    }
+
+    #[test]
+    pub fn test_invalid_dash_patterns() {
+        assert!(is_valid_dash_pattern((4.0, 2.0)));
+        for pattern in [
+            (0.0, 2.0),
+            (4.0, 0.0),
+            (-4.0, 2.0),
+            (f32::NAN, 2.0),
+            (4.0, f32::INFINITY),
+        ] {
+            assert!(!is_valid_dash_pattern(pattern));
+        }
+    }
+
+    #[test]
+    pub fn test_route_cache_avoids_moved_nodes() {
+        let mut nodes = SlotMap::<NodeId, ()>::with_key();
+        let node = nodes.insert(());
+        let mut inputs = SlotMap::<InputId, ()>::with_key();
+        let (input, other_input) = (inputs.insert(()), inputs.insert(()));
+        let src = Pos2::new(0.0, 0.0);
+        let dst = Pos2::new(400.0, 0.0);
+        let orientations = [NodeOrientation::Horizontal; 2];
+        let crosses = |route: &[Pos2], rect: Rect| {
+            route
+                .windows(2)
+                .any(|segment| rect.intersects(Rect::from_two_pos(segment[0], segment[1])))
+        };
+
+        let mut cache = WireRouteCache::default();
+        let mut node_rects = NodeRects::new();
+        node_rects.insert(
+            node,
+            Rect::from_min_size(Pos2::new(150.0, 200.0), Vec2::splat(100.0)),
+        );
+        let route = cache.route(Some(input), src, dst, orientations, &node_rects);
+        let moved = Rect::from_min_max(Pos2::new(150.0, -50.0), Pos2::new(250.0, 50.0));
+        assert!(crosses(&route, moved));
+
+        // A wire far from the node, whose cached route is marked to tell it
+        // apart from a new one
+        let (other_src, other_dst) = (Pos2::new(0.0, 1000.0), Pos2::new(400.0, 1000.0));
+        cache.route(
+            Some(other_input),
+            other_src,
+            other_dst,
+            orientations,
+            &node_rects,
+        );
+        let cached = cache.routes.get_mut(&other_input).unwrap();
+        cached.points.push(other_dst);
+        let marked = cached.points.clone();
+
+        // The node moves onto the first wire, which goes around it, while the
+        // other one keeps its route
+        node_rects.insert(node, moved);
+        let route = cache.route(Some(input), src, dst, orientations, &node_rects);
+        assert!(!crosses(&route, moved));
+        let other_route = cache.route(
+            Some(other_input),
+            other_src,
+            other_dst,
+            orientations,
+            &node_rects,
+        );
+        assert_eq!(other_route, marked);
+    }
+}
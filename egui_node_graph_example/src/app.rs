@@ -31,7 +31,7 @@ pub enum MyDataType {
 /// this library makes no attempt to check this consistency. For instance, it is
 /// up to the user code in this example to make sure no parameter is created
 /// with a DataType of Scalar and a ValueType of Vec2.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub enum MyValueType {
     Vec2 { value: egui::Vec2 },
@@ -142,19 +142,43 @@ impl NodeTemplateTrait for MyNodeTemplate {
         })
     }
 
+    // Identifies the template in the favourite and recently used templates of
+    // the node finder, which are saved with the app. Unlike the labels, the
+    // variant names won't change if the app gets translated.
+    fn node_template_id(&self, _user_state: &mut Self::UserState) -> Cow<'_, str> {
+        Cow::Owned(format!("{self:?}"))
+    }
+
     // this is what allows the library to show collapsible lists in the node finder.
+    // Categories are nested by separating their levels with a `/`.
     fn node_finder_categories(&self, _user_state: &mut Self::UserState) -> Vec<&'static str> {
         match self {
             MyNodeTemplate::MakeScalar
             | MyNodeTemplate::AddScalar
-            | MyNodeTemplate::SubtractScalar => vec!["Scalar"],
+            | MyNodeTemplate::SubtractScalar => vec!["Math/Scalar"],
             MyNodeTemplate::MakeVector
             | MyNodeTemplate::AddVector
-            | MyNodeTemplate::SubtractVector => vec!["Vector"],
-            MyNodeTemplate::VectorTimesScalar => vec!["Vector", "Scalar"],
+            | MyNodeTemplate::SubtractVector => vec!["Math/Vector"],
+            MyNodeTemplate::VectorTimesScalar => vec!["Math/Vector", "Math/Scalar"],
         }
     }
 
+    // Shown in the node finder when hovering a template.
+    fn description(&self, _user_state: &mut Self::UserState) -> Option<String> {
+        Some(
+            match self {
+                MyNodeTemplate::MakeScalar => "Outputs a constant scalar.",
+                MyNodeTemplate::AddScalar => "Adds two scalars.",
+                MyNodeTemplate::SubtractScalar => "Subtracts B from A.",
+                MyNodeTemplate::MakeVector => "Builds a vector from its x and y components.",
+                MyNodeTemplate::AddVector => "Adds two vectors.",
+                MyNodeTemplate::SubtractVector => "Subtracts v2 from v1.",
+                MyNodeTemplate::VectorTimesScalar => "Scales a vector by a scalar.",
+            }
+            .to_owned(),
+        )
+    }
+
     fn node_graph_label(&self, user_state: &mut Self::UserState) -> String {
         // It's okay to delegate this to node_finder_label if you don't want to
         // show different names in the node finder and the node itself.
@@ -281,6 +305,41 @@ impl NodeTemplateIter for AllMyNodeTemplates {
     }
 }
 
+/// The commands this example adds to the node finder. Type `>` in the node
+/// finder to list them, after the built-in ones.
+#[derive(Clone, Copy, Debug)]
+pub enum MyCommand {
+    EvaluateSelected,
+    StopEvaluating,
+}
+
+impl EditorCommandTrait for MyCommand {
+    type NodeData = MyNodeData;
+
+    fn label(&self, _user_state: &mut MyGraphState) -> Cow<'_, str> {
+        Cow::Borrowed(match self {
+            MyCommand::EvaluateSelected => "Evaluate selected node",
+            MyCommand::StopEvaluating => "Stop evaluating",
+        })
+    }
+
+    fn run(
+        &self,
+        _graph: &mut MyGraph,
+        selected_nodes: &mut std::collections::HashSet<NodeId>,
+        _user_state: &mut MyGraphState,
+    ) -> Vec<NodeResponse<MyResponse, MyNodeData>> {
+        let response = match self {
+            MyCommand::EvaluateSelected => match selected_nodes.iter().next() {
+                Some(node) => MyResponse::SetActiveNode(*node),
+                None => return vec![],
+            },
+            MyCommand::StopEvaluating => MyResponse::ClearActiveNode,
+        };
+        vec![NodeResponse::User(response)]
+    }
+}
+
 impl WidgetValueTrait for MyValueType {
     type Response = MyResponse;
     type UserState = MyGraphState;
@@ -369,6 +428,25 @@ impl NodeDataTrait for MyNodeData {
 
         responses
     }
+
+    // Items added to the menu opened by right-clicking a node, after the
+    // built-in ones.
+    fn context_menu_ui(
+        &self,
+        ui: &mut egui::Ui,
+        node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType, Self::UserState>,
+        _user_state: &mut Self::UserState,
+    ) -> Vec<NodeResponse<Self::Response, Self>>
+    where
+        MyResponse: UserResponseTrait,
+    {
+        ui.separator();
+        if ui.button("👁 Set active").clicked() {
+            return vec![NodeResponse::User(MyResponse::SetActiveNode(node_id))];
+        }
+        vec![]
+    }
 }
 
 type MyGraph = Graph<MyNodeData, MyDataType, MyValueType, MyGraphState>;
@@ -416,8 +494,31 @@ impl eframe::App for NodeGraphExample {
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 egui::widgets::global_theme_preference_switch(ui);
+                ui.separator();
+                ui.selectable_value(
+                    &mut self.state.node_orientation,
+                    NodeOrientation::Horizontal,
+                    "Left to right",
+                );
+                ui.selectable_value(
+                    &mut self.state.node_orientation,
+                    NodeOrientation::Vertical,
+                    "Top to bottom",
+                );
             });
         });
+        // Templates can be dragged from here onto the graph
+        egui::SidePanel::left("palette").show(ctx, |ui| {
+            self.state
+                .draw_node_palette(ui, AllMyNodeTemplates, &mut self.user_state);
+        });
+        // Edits the inputs of the selected nodes, including the ones that
+        // aren't shown inline
+        let inspector_responses = egui::SidePanel::right("inspector")
+            .show(ctx, |ui| {
+                self.state.draw_inspector(ui, &mut self.user_state)
+            })
+            .inner;
         let graph_response = egui::CentralPanel::default()
             .show(ctx, |ui| {
                 #[cfg(feature = "persistence")]
@@ -446,9 +547,7 @@ impl eframe::App for NodeGraphExample {
                         if let Ok(serialized_state) =
                             serde_json::to_string_pretty(&self.state.from_selected())
                         {
-                            ui.output_mut(|output| {
-                                output.copied_text = serialized_state;
-                            });
+                            ui.ctx().copy_text(serialized_state);
                         }
                     } else if !pasted.is_empty() {
                         if let Ok(mut deserialized_editor_state) = serde_json::from_str(&pasted) {
@@ -459,25 +558,30 @@ impl eframe::App for NodeGraphExample {
                         }
                     }
 
-                    self.state.draw_graph_editor(
+                    self.state.draw_graph_editor_with_commands(
                         ui,
                         AllMyNodeTemplates,
+                        vec![MyCommand::EvaluateSelected, MyCommand::StopEvaluating],
                         &mut self.user_state,
                         responses,
                     )
                 }
                 #[cfg(not(feature = "persistence"))]
                 {
-                    self.state.draw_graph_editor(
+                    self.state.draw_graph_editor_with_commands(
                         ui,
                         AllMyNodeTemplates,
+                        vec![MyCommand::EvaluateSelected, MyCommand::StopEvaluating],
                         &mut self.user_state,
                         Vec::default(),
                     )
                 }
             })
             .inner;
-        for node_response in graph_response.node_responses {
+        for node_response in inspector_responses
+            .into_iter()
+            .chain(graph_response.node_responses)
+        {
             // Here, we ignore all other graph events. But you may find
             // some use for them. For example, by playing a sound when a new
             // connection is created
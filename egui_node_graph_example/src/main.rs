@@ -16,7 +16,7 @@ fn main() {
             cc.egui_ctx.set_visuals(Visuals::dark());
             #[cfg(feature = "persistence")]
             {
-                Box::new(NodeGraphExample::new(cc))
+                Ok(Box::new(NodeGraphExample::new(cc)))
             }
             #[cfg(not(feature = "persistence"))]
             Ok(Box::<NodeGraphExample>::default())